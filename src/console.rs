@@ -0,0 +1,54 @@
+//! Printing that can be held back while something else is using the terminal, e.g. the
+//! terminal renderer, so it doesn't end up drawn over the game
+
+use std::sync::Mutex;
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Clone, Debug, PartialEq)]
+enum Line {
+    Out(String),
+    Err(String),
+}
+
+/// Lines waiting to be printed, or `None` if they're printed straight away
+static HELD: Mutex<Option<Vec<Line>>> = Mutex::new(None);
+
+/// Keep anything printed from now on until `release`
+pub fn hold() {
+    let mut held = HELD.lock().unwrap_or_else(|e| e.into_inner());
+    held.get_or_insert_with(Vec::new);
+}
+
+/// Print everything held, in order, and go back to printing straight away
+pub fn release() {
+    let held = HELD.lock().unwrap_or_else(|e| e.into_inner()).take();
+    for line in held.into_iter().flatten() {
+        print_line(line);
+    }
+}
+
+/// To stdout
+pub fn say(text: String) {
+    print_or_hold(Line::Out(text));
+}
+
+/// To stderr, as a warning
+pub fn warn(text: String) {
+    print_or_hold(Line::Err(format!("warning: {}", text)));
+}
+
+fn print_or_hold(line: Line) {
+    match HELD.lock().unwrap_or_else(|e| e.into_inner()).as_mut() {
+        Some(held) => held.push(line),
+        None => print_line(line),
+    }
+}
+
+fn print_line(line: Line) {
+    match line {
+        Line::Out(text) => println!("{}", text),
+        Line::Err(text) => eprintln!("{}", text),
+    }
+}
@@ -0,0 +1,22 @@
+use crate::console::{hold, release, say, warn, Line, HELD};
+
+#[test]
+fn held_lines_wait_for_release() {
+    hold();
+    say("game over! score: 10".to_string());
+    warn("music stopped".to_string());
+
+    let held = HELD.lock().unwrap().clone().unwrap();
+    // other tests might be printing too
+    assert!(held.contains(&Line::Out("game over! score: 10".to_string())));
+    let warning = held
+        .iter()
+        .position(|line| line == &Line::Err("warning: music stopped".to_string()));
+    let game_over = held
+        .iter()
+        .position(|line| line == &Line::Out("game over! score: 10".to_string()));
+    assert!(game_over < warning);
+
+    release();
+    assert_eq!(*HELD.lock().unwrap(), None);
+}
@@ -1,25 +1,68 @@
+use crossterm::event::{
+    self as terminal_event, KeyCode, KeyEventKind, KeyModifiers, MouseEventKind,
+};
 use winit::{
-    dpi::PhysicalPosition,
-    event::{ElementState, Event, MouseButton, WindowEvent},
+    dpi::{PhysicalPosition, PhysicalSize},
+    event::{ElementState, Event, KeyEvent, MouseButton, WindowEvent},
+    keyboard::{Key, NamedKey},
 };
 
+use crate::replay::Tick;
+use crate::settings::Settings;
+use crate::view::audio::{Volume, VOLUME_STEP};
 use crate::view::ViewState;
 
+#[cfg(test)]
+mod tests;
+
+/// How far one key press moves the paddle when playing in the terminal
+pub const TERMINAL_PADDLE_STEP: f32 = 0.1;
+
 pub struct ControlState {
     mouse_x_relative: f32,
     mouse_y_relative: f32,
 
     lmb_up_frame: Option<u64>,
     lmb_down_frame: Option<u64>,
+
+    /// Latched by the pause keys, losing focus, or the window being hidden or minimized, and
+    /// only cleared by the pause keys
+    pause_requested: bool,
+
+    pause_on_focus_loss: bool,
+
+    volume: Volume,
+    /// What the volume started at, so only what's changed with the keys gets saved
+    initial_volume: Volume,
+
+    /// Only ever set when playing in the terminal, windows are closed instead
+    quit_requested: bool,
+    /// Frame the screenshot key was last pressed on
+    screenshot_frame: Option<u64>,
+    /// Frame the theme key was last pressed on
+    theme_frame: Option<u64>,
+    debug_overlay: bool,
 }
 impl ControlState {
-    pub fn new() -> ControlState {
+    pub fn new(settings: &Settings) -> ControlState {
         ControlState {
             mouse_x_relative: 0.0,
             mouse_y_relative: 0.0,
 
             lmb_up_frame: None,
             lmb_down_frame: None,
+
+            pause_requested: false,
+
+            pause_on_focus_loss: settings.control.pause_on_focus_loss,
+
+            volume: Volume::from(&settings.audio),
+            initial_volume: Volume::from(&settings.audio),
+
+            quit_requested: false,
+            screenshot_frame: None,
+            theme_frame: None,
+            debug_overlay: settings.video.debug_overlay,
         }
     }
 
@@ -38,12 +81,85 @@ impl ControlState {
                 } => {
                     self.on_mouse_input(view_state, s, b);
                 }
+                WindowEvent::KeyboardInput { event: e, .. } => {
+                    self.on_keyboard_input(view_state, e);
+                }
+                WindowEvent::Focused(f) => {
+                    self.on_focus_changed(f);
+                }
+                WindowEvent::Occluded(o) => {
+                    self.on_occlusion_changed(o);
+                }
+                WindowEvent::Resized(size) => {
+                    self.on_resized(size);
+                }
+                _ => (),
+            },
+            _ => (),
+        }
+    }
+
+    /// Handle input read from the terminal, when playing in one
+    pub fn update_terminal(&mut self, view_state: &ViewState, event: terminal_event::Event) {
+        match event {
+            terminal_event::Event::Key(key_event) => {
+                self.on_terminal_key(view_state, key_event);
+            }
+            terminal_event::Event::Mouse(mouse_event) => match mouse_event.kind {
+                MouseEventKind::Moved | MouseEventKind::Drag(_) => {
+                    // the middle of the character cell, in half block pixels
+                    let p = PhysicalPosition::new(
+                        mouse_event.column as f64 + 0.5,
+                        mouse_event.row as f64 * 2.0 + 1.0,
+                    );
+                    self.on_cursor_moved(view_state, p);
+                }
+                MouseEventKind::Down(terminal_event::MouseButton::Left) => {
+                    self.on_mouse_input(view_state, ElementState::Pressed, MouseButton::Left);
+                }
+                MouseEventKind::Up(terminal_event::MouseButton::Left) => {
+                    self.on_mouse_input(view_state, ElementState::Released, MouseButton::Left);
+                }
                 _ => (),
             },
+            terminal_event::Event::FocusGained => self.on_focus_changed(true),
+            terminal_event::Event::FocusLost => self.on_focus_changed(false),
             _ => (),
         }
     }
 
+    /// Stand in for the user, with input recorded earlier
+    pub fn replay(&mut self, tick: &Tick) {
+        self.mouse_x_relative = tick.mouse_x;
+        self.mouse_y_relative = tick.mouse_y;
+        (self.lmb_down_frame, self.lmb_up_frame) = if tick.clicked {
+            (Some(0), Some(0))
+        } else {
+            (None, None)
+        };
+        self.pause_requested = tick.paused;
+    }
+
+    /// Has the user asked for a screenshot of frame number `frame`?
+    pub fn screenshot_requested(&self, frame: u64) -> bool {
+        self.screenshot_frame == Some(frame)
+    }
+
+    /// Has the user asked for the next theme on frame number `frame`?
+    pub fn theme_change_requested(&self, frame: u64) -> bool {
+        self.theme_frame == Some(frame)
+    }
+
+    /// Should collision boxes and frame stats be drawn over the game?
+    pub fn debug_overlay(&self) -> bool {
+        self.debug_overlay
+    }
+
+    /// Has the user asked to quit? Only ever true in the terminal.
+    pub fn quit_requested(&self) -> bool {
+        self.quit_requested
+    }
+
     /// Has the user clicked this frame?
     pub fn clicked(&self) -> bool {
         if let Some(up_frame) = self.lmb_up_frame
@@ -55,6 +171,23 @@ impl ControlState {
         }
     }
 
+    /// Should the game be paused?
+    ///
+    /// True if the user asked for a pause, or focus was lost or the window hidden or minimized
+    /// since last resuming. Showing the window again doesn't resume by itself.
+    pub fn paused(&self) -> bool {
+        self.pause_requested
+    }
+
+    /// How loud the user wants things
+    pub fn volume(&self) -> Volume {
+        self.volume
+    }
+
+    pub fn initial_volume(&self) -> Volume {
+        self.initial_volume
+    }
+
     /// Return the current position of the mouse, relative to the playfield
     pub fn mouse_coords(&self) -> (f32, f32) {
         (self.mouse_x_relative, self.mouse_y_relative)
@@ -75,6 +208,91 @@ impl ControlState {
         }
     }
 
+    fn on_keyboard_input(&mut self, view_state: &ViewState, event: KeyEvent) {
+        if event.state != ElementState::Pressed {
+            return;
+        }
+        // toggles ignore key repeat, holding a key down shouldn't flicker things on and off
+        match event.logical_key.as_ref() {
+            Key::Named(NamedKey::Escape) | Key::Character("p" | "P") if !event.repeat => {
+                self.toggle_pause()
+            }
+            Key::Character("m" | "M") if !event.repeat => self.volume.muted = !self.volume.muted,
+            Key::Character("-") => self.change_volume(-VOLUME_STEP),
+            Key::Character("=" | "+") => self.change_volume(VOLUME_STEP),
+            Key::Named(NamedKey::F12) if !event.repeat => {
+                self.screenshot_frame = Some(view_state.frame_count())
+            }
+            Key::Character("t" | "T") if !event.repeat => {
+                self.theme_frame = Some(view_state.frame_count())
+            }
+            Key::Named(NamedKey::F3) if !event.repeat => self.debug_overlay = !self.debug_overlay,
+            _ => (),
+        }
+    }
+
+    fn on_terminal_key(&mut self, view_state: &ViewState, event: terminal_event::KeyEvent) {
+        if event.kind == KeyEventKind::Release {
+            return;
+        }
+        let repeat = event.kind == KeyEventKind::Repeat;
+        match event.code {
+            KeyCode::Char('c') if event.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.quit_requested = true
+            }
+            KeyCode::Char('q' | 'Q') => self.quit_requested = true,
+            KeyCode::Esc | KeyCode::Char('p' | 'P') if !repeat => self.toggle_pause(),
+            KeyCode::Char('m' | 'M') if !repeat => self.volume.muted = !self.volume.muted,
+            KeyCode::Char('-') => self.change_volume(-VOLUME_STEP),
+            KeyCode::Char('=' | '+') => self.change_volume(VOLUME_STEP),
+            KeyCode::F(12) if !repeat => self.screenshot_frame = Some(view_state.frame_count()),
+            KeyCode::Char('t' | 'T') if !repeat => {
+                self.theme_frame = Some(view_state.frame_count())
+            }
+            KeyCode::F(3) if !repeat => self.debug_overlay = !self.debug_overlay,
+            KeyCode::Left | KeyCode::Char('a' | 'A') => self.move_paddle(-TERMINAL_PADDLE_STEP),
+            KeyCode::Right | KeyCode::Char('d' | 'D') => self.move_paddle(TERMINAL_PADDLE_STEP),
+            // there's no releasing a key in most terminals, so a press is a whole click
+            KeyCode::Char(' ') | KeyCode::Enter if !repeat => {
+                self.on_mouse_input(view_state, ElementState::Pressed, MouseButton::Left);
+                self.on_mouse_input(view_state, ElementState::Released, MouseButton::Left);
+            }
+            _ => (),
+        }
+    }
+
+    fn toggle_pause(&mut self) {
+        self.pause_requested = !self.pause_requested;
+    }
+
+    fn move_paddle(&mut self, step: f32) {
+        self.mouse_x_relative = (self.mouse_x_relative + step).clamp(-1.0, 1.0);
+    }
+
+    fn change_volume(&mut self, step: f32) {
+        self.volume.master = (self.volume.master + step).clamp(0.0, 1.0);
+    }
+
+    fn on_focus_changed(&mut self, focused: bool) {
+        // stays paused once focus comes back, the user resumes explicitly
+        if !focused && self.pause_on_focus_loss {
+            self.pause_requested = true;
+        }
+    }
+
+    fn on_occlusion_changed(&mut self, occluded: bool) {
+        if occluded {
+            self.pause_requested = true;
+        }
+    }
+
+    fn on_resized(&mut self, size: PhysicalSize<u32>) {
+        // most platforms report minimization as a resize to nothing
+        if size.width == 0 || size.height == 0 {
+            self.pause_requested = true;
+        }
+    }
+
     fn on_cursor_moved(&mut self, view_state: &ViewState, p: PhysicalPosition<f64>) {
         // let window_width = view_state.window_width;
         // let window_height = view_state.window_height;
@@ -0,0 +1,48 @@
+use winit::dpi::PhysicalSize;
+
+use crate::control::ControlState;
+use crate::settings::Settings;
+
+fn new_control_state(pause_on_focus_loss: bool) -> ControlState {
+    let mut settings = Settings::default();
+    settings.control.pause_on_focus_loss = pause_on_focus_loss;
+    ControlState::new(&settings)
+}
+
+#[test]
+fn focus_loss_pauses_until_resumed() {
+    let mut control_state = new_control_state(true);
+    assert!(!control_state.paused());
+
+    control_state.on_focus_changed(false);
+    assert!(control_state.paused());
+    // coming back doesn't resume by itself
+    control_state.on_focus_changed(true);
+    assert!(control_state.paused());
+    control_state.toggle_pause();
+    assert!(!control_state.paused());
+
+    let mut control_state = new_control_state(false);
+    control_state.on_focus_changed(false);
+    assert!(!control_state.paused());
+}
+
+#[test]
+fn hidden_windows_stay_paused() {
+    let mut control_state = new_control_state(false);
+
+    control_state.on_occlusion_changed(true);
+    assert!(control_state.paused());
+    // showing it again doesn't resume by itself
+    control_state.on_occlusion_changed(false);
+    assert!(control_state.paused());
+    control_state.toggle_pause();
+    assert!(!control_state.paused());
+
+    control_state.on_resized(PhysicalSize::new(0, 0));
+    assert!(control_state.paused());
+    control_state.on_resized(PhysicalSize::new(800, 600));
+    assert!(control_state.paused());
+    control_state.toggle_pause();
+    assert!(!control_state.paused());
+}
@@ -0,0 +1,205 @@
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::control::ControlState;
+use crate::logic::LogicState;
+use crate::replay::Replay;
+use crate::settings::{ExportFormat, Settings, SettingsError};
+use crate::view::audio::mixer::MixingBackend;
+use crate::view::audio::AudioState;
+use crate::view::video::atlas::{encode_png, Image};
+use crate::view::video::scene::Scene;
+use crate::view::video::software::SoftwareRenderer;
+use crate::view::video::Renderer;
+
+#[cfg(test)]
+mod tests;
+
+pub const FPS: u32 = 60;
+
+#[derive(Debug)]
+pub enum ExportError {
+    Settings(SettingsError),
+    Io(PathBuf, io::Error),
+    Png(PathBuf, png::EncodingError),
+    Wav(PathBuf, hound::Error),
+}
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExportError::Settings(e) => write!(f, "the replay's settings don't work: {}", e),
+            ExportError::Io(path, e) => write!(f, "unable to write {}: {}", path.display(), e),
+            ExportError::Png(path, e) => write!(f, "unable to encode {}: {}", path.display(), e),
+            ExportError::Wav(path, e) => write!(f, "unable to write {}: {}", path.display(), e),
+        }
+    }
+}
+impl Error for ExportError {}
+
+/// What ended up on disk
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Exported {
+    pub frames: u64,
+    pub duration: Duration,
+}
+
+/// Play `replay` back as fast as possible, drawing a frame every `1 / fps` seconds of game time
+/// to `settings.export.dir`, along with the sound effects in `audio.wav`
+///
+/// The game steps exactly as it did when recorded, however long each step was, so it plays out
+/// the same. Only the frames are evenly spaced.
+pub fn export(replay: &Replay, settings: &Settings) -> Result<Exported, ExportError> {
+    let mut settings = settings.clone();
+    settings.logic = replay.logic.clone();
+    settings.validate().map_err(ExportError::Settings)?;
+
+    let dir = settings.export.dir.clone();
+    fs::create_dir_all(&dir).map_err(|e| ExportError::Io(dir.clone(), e))?;
+    let (width, height) = (settings.video.window_width, settings.video.window_height);
+    let mut frames = FrameWriter::new(
+        &dir,
+        settings.export.format,
+        width,
+        height,
+        settings.export.fps,
+    )?;
+
+    let mut logic_state = LogicState::new(&settings);
+    let mut control_state = ControlState::new(&settings);
+    let mut scene = Scene::new(&settings);
+    let mut renderer = SoftwareRenderer::new(width, height).with_post(settings.video.post.clone());
+    if let Some(atlas) = scene.atlas() {
+        renderer.set_atlas(atlas);
+    }
+    let mixer = MixingBackend::new(&settings);
+    let mut audio_state = AudioState::with_backend(Box::new(mixer.clone()), &settings);
+
+    // nothing cares what the time actually is, only how far apart ticks are
+    let start = Instant::now();
+    let frame_time = Duration::from_secs(1) / settings.export.fps;
+    let mut time = Duration::ZERO;
+    let mut next_frame = Duration::ZERO;
+    for tick in replay.ticks.iter() {
+        time += tick.delta_t;
+        control_state.replay(tick);
+        logic_state.update(&control_state, start + time, tick.delta_t);
+        scene.update(&logic_state, start + time);
+        mixer.set_time(time);
+        audio_state.update(&logic_state, &control_state);
+        logic_state.clear_events();
+
+        while next_frame <= time {
+            renderer.render(&scene.draw_list(&logic_state));
+            frames.write(renderer.image())?;
+            next_frame += frame_time;
+        }
+    }
+    frames.finish()?;
+
+    let exported = Exported {
+        frames: frames.count,
+        duration: frame_time * frames.count as u32,
+    };
+    let wav = dir.join("audio.wav");
+    mixer
+        .save_wav(&wav, exported.duration)
+        .map_err(|e| ExportError::Wav(wav, e))?;
+    Ok(exported)
+}
+
+enum Output {
+    Png(PathBuf),
+    Y4m(PathBuf, BufWriter<File>),
+}
+
+struct FrameWriter {
+    output: Output,
+    count: u64,
+}
+impl FrameWriter {
+    fn new(
+        dir: &Path,
+        format: ExportFormat,
+        width: u32,
+        height: u32,
+        fps: u32,
+    ) -> Result<FrameWriter, ExportError> {
+        let output = match format {
+            ExportFormat::Png => Output::Png(dir.to_owned()),
+            ExportFormat::Y4m => {
+                let path = dir.join("video.y4m");
+                let file = File::create(&path).map_err(|e| ExportError::Io(path.clone(), e))?;
+                let mut writer = BufWriter::new(file);
+                writer
+                    .write_all(y4m_header(width, height, fps).as_bytes())
+                    .map_err(|e| ExportError::Io(path.clone(), e))?;
+                Output::Y4m(path, writer)
+            }
+        };
+        Ok(FrameWriter { output, count: 0 })
+    }
+
+    fn write(&mut self, image: &Image) -> Result<(), ExportError> {
+        self.count += 1;
+        match &mut self.output {
+            Output::Png(dir) => {
+                let path = dir.join(frame_name(self.count));
+                let file = File::create(&path).map_err(|e| ExportError::Io(path.clone(), e))?;
+                encode_png(image, BufWriter::new(file)).map_err(|e| ExportError::Png(path, e))
+            }
+            Output::Y4m(path, writer) => {
+                write_y4m_frame(image, writer).map_err(|e| ExportError::Io(path.clone(), e))
+            }
+        }
+    }
+
+    fn finish(&mut self) -> Result<(), ExportError> {
+        match &mut self.output {
+            Output::Png(_) => Ok(()),
+            Output::Y4m(path, writer) => {
+                writer.flush().map_err(|e| ExportError::Io(path.clone(), e))
+            }
+        }
+    }
+}
+
+/// e.g. `frame-000001.png`, counting from 1 like most video tools expect
+pub fn frame_name(number: u64) -> String {
+    format!("frame-{:06}.png", number)
+}
+
+/// Full resolution color, square pixels, progressive
+pub fn y4m_header(width: u32, height: u32, fps: u32) -> String {
+    format!("YUV4MPEG2 W{} H{} F{}:1 Ip A1:1 C444\n", width, height, fps)
+}
+
+/// One frame: the Y plane, then U, then V, with alpha ignored
+pub fn write_y4m_frame(image: &Image, mut writer: impl Write) -> io::Result<()> {
+    let pixels: Vec<[u8; 3]> = image
+        .pixels
+        .chunks_exact(4)
+        .map(|rgba| ycbcr([rgba[0], rgba[1], rgba[2]]))
+        .collect();
+
+    writer.write_all(b"FRAME\n")?;
+    for plane in 0..3 {
+        let bytes: Vec<u8> = pixels.iter().map(|pixel| pixel[plane]).collect();
+        writer.write_all(&bytes)?;
+    }
+    Ok(())
+}
+
+/// BT.601 in the limited range, which is what players assume when nothing says otherwise
+pub fn ycbcr([r, g, b]: [u8; 3]) -> [u8; 3] {
+    let [r, g, b] = [r, g, b].map(|c| c as f32 / 255.0);
+    [
+        16.0 + 65.481 * r + 128.553 * g + 24.966 * b,
+        128.0 - 37.797 * r - 74.203 * g + 112.0 * b,
+        128.0 + 112.0 * r - 93.786 * g - 18.214 * b,
+    ]
+    .map(|c| c.round() as u8)
+}
@@ -0,0 +1,109 @@
+use crate::export::{export, frame_name, write_y4m_frame, y4m_header, ycbcr};
+use crate::replay::{Replay, Tick};
+use crate::settings::{ExportFormat, LogicSettings, Settings};
+use crate::view::video::atlas::Image;
+use std::fs;
+use std::time::Duration;
+
+#[test]
+fn y4m_frames_are_planar() {
+    assert_eq!(ycbcr([0, 0, 0]), [16, 128, 128]);
+    assert_eq!(ycbcr([255, 255, 255]), [235, 128, 128]);
+
+    let image = Image {
+        width: 2,
+        height: 1,
+        pixels: vec![0, 0, 0, 255, 255, 255, 255, 255],
+    };
+    let mut bytes = Vec::new();
+    write_y4m_frame(&image, &mut bytes).unwrap();
+    assert_eq!(bytes, b"FRAME\n\x10\xeb\x80\x80\x80\x80");
+
+    assert_eq!(
+        y4m_header(640, 480, 60),
+        "YUV4MPEG2 W640 H480 F60:1 Ip A1:1 C444\n"
+    );
+}
+
+#[test]
+fn frames_are_evenly_spaced_whatever_the_ticks() {
+    let dir = std::env::temp_dir().join("breakout-export-test");
+    let _ = fs::remove_dir_all(&dir);
+
+    let mut settings = Settings::default();
+    settings.video.window_width = 32;
+    settings.video.window_height = 24;
+    settings.export.dir = dir.clone();
+    settings.export.format = ExportFormat::Png;
+    settings.export.fps = 10;
+
+    // a second of game, in uneven steps
+    let mut replay = Replay::new(LogicSettings {
+        seed: Some(1),
+        ..LogicSettings::default()
+    });
+    for delta_t in [0, 30, 250, 20, 300, 400] {
+        replay.record(Tick {
+            delta_t: Duration::from_millis(delta_t),
+            mouse_x: 0.0,
+            mouse_y: 0.0,
+            clicked: true,
+            paused: false,
+        });
+    }
+
+    let exported = export(&replay, &settings).unwrap();
+    assert_eq!(exported.frames, 11);
+    assert_eq!(exported.duration, Duration::from_millis(1100));
+    assert!(dir.join(frame_name(11)).is_file());
+    assert!(!dir.join(frame_name(12)).is_file());
+
+    let wav = hound::WavReader::open(dir.join("audio.wav")).unwrap();
+    assert_eq!(wav.spec().channels, 2);
+    assert_eq!(
+        wav.duration(),
+        (1.1 * settings.audio.sample_rate as f64).round() as u32
+    );
+}
+
+#[test]
+fn reduced_motion_exports_the_game_as_recorded() {
+    // long enough for the ball to break a brick, and the hit-stop to hold it up after
+    let mut replay = Replay::new(LogicSettings {
+        seed: Some(1),
+        hit_stop: 0.1,
+        ..LogicSettings::default()
+    });
+    for _ in 0..300 {
+        replay.record(Tick {
+            delta_t: Duration::from_millis(10),
+            mouse_x: 0.0,
+            mouse_y: 0.0,
+            clicked: true,
+            paused: false,
+        });
+    }
+
+    let video = |reduced_motion: bool| {
+        let dir = std::env::temp_dir().join(format!(
+            "breakout-export-reduced-motion-test-{}",
+            reduced_motion
+        ));
+        let _ = fs::remove_dir_all(&dir);
+
+        let mut settings = Settings::default();
+        settings.video.window_width = 8;
+        settings.video.window_height = 6;
+        settings.video.reduced_motion = reduced_motion;
+        // so only the hit-stop could make the frames differ
+        settings.video.screen_shake = 0.0;
+        settings.export.dir = dir.clone();
+        settings.export.format = ExportFormat::Y4m;
+        settings.export.fps = 10;
+        settings.apply_reduced_motion();
+
+        export(&replay, &settings).unwrap();
+        fs::read(dir.join("video.y4m")).unwrap()
+    };
+    assert!(video(true) == video(false));
+}
@@ -5,3 +5,15 @@ pub mod logic;
 pub mod view;
 
 pub mod control;
+
+pub mod settings;
+
+pub mod replay;
+
+pub mod pacing;
+
+pub mod stats;
+
+pub mod export;
+
+pub mod console;
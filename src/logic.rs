@@ -1,12 +1,32 @@
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime};
 
+use crate::console;
 use crate::control::ControlState;
+use crate::settings::{LogicSettings, Ruleset, Settings};
 
 pub mod game_objs;
 use game_objs::GameObject;
 
 pub mod interaction;
-use interaction::Bounce;
+use interaction::{Bounce, Impact};
+
+pub mod rng;
+use rng::Rng;
+
+#[cfg(test)]
+mod tests;
+
+/// Default seconds of hit-stop after breaking a brick
+pub const HIT_STOP: f32 = 0.04;
+
+/// Where we're at in a game
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Phase {
+    /// Waiting for a click to start
+    Menu,
+    Playing,
+    GameOver,
+}
 
 /// Game state
 #[derive(Clone, Debug, PartialEq)]
@@ -16,40 +36,90 @@ pub struct LogicState {
     ball: GameObject,
     bricks: Vec<GameObject>,
 
-    bounce: Option<Bounce>,
+    /// What's happened since the events were last cleared, which may be several ticks
+    impacts: Vec<Impact>,
+    destroyed_bricks: Vec<GameObject>,
+    ball_lost: bool,
 
     balls_remaining: u32,
     score: u32,
 
     too_late: bool,
 
+    /// Calls to `update` so far, paused or not
+    ticks: u64,
     game_started: Option<Instant>,
+    game_over: bool,
+    paused: bool,
+    /// Time spent actually playing, i.e. excluding time spent paused
+    time_played: Duration,
+    /// Time left frozen after breaking a brick
+    hit_stop: Duration,
+
+    rng: Rng,
+    settings: LogicSettings,
 }
 impl LogicState {
-    pub fn new() -> LogicState {
-        LogicState {
-            paddle: GameObject::paddle(),
-            playfield: GameObject::playfield(),
-            ball: GameObject::ball(2),
-            bricks: GameObject::bricks(),
+    pub fn new(settings: &Settings) -> LogicState {
+        let mut settings = settings.logic.clone();
+
+        // no seed means a different game every time
+        let seed = settings.seed.unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map(|d| d.as_nanos() as u64)
+                .unwrap_or_default()
+        });
+        // remembered so the game can be replayed
+        settings.seed = Some(seed);
+        let mut rng = Rng::new(seed);
 
-            balls_remaining: 2,
+        LogicState {
+            paddle: GameObject::paddle(&settings),
+            playfield: GameObject::playfield(&settings),
+            ball: GameObject::ball(
+                settings.balls,
+                launch_jitter(&mut rng),
+                ball_speed(&settings),
+                &settings,
+            ),
+            bricks: GameObject::bricks(&settings),
+
+            balls_remaining: settings.balls,
             score: 0,
 
             too_late: false,
-            bounce: None,
+            impacts: Vec::new(),
+            destroyed_bricks: Vec::new(),
+            ball_lost: false,
 
+            ticks: 0,
             game_started: None,
+            game_over: false,
+            paused: false,
+            time_played: Duration::ZERO,
+            hit_stop: Duration::ZERO,
+
+            rng,
+            settings,
         }
     }
 
     pub fn update(&mut self, control_state: &ControlState, now: Instant, delta_t: Duration) {
+        self.ticks += 1;
         if self.game_started.is_none() && control_state.clicked() {
             self.game_started = Some(now);
         }
 
-        if self.game_started.is_some() {
-            self.game_tick(control_state, now, delta_t);
+        self.paused = self.game_started.is_some() && control_state.paused();
+
+        if self.game_started.is_some() && !self.paused {
+            if self.hit_stop > Duration::ZERO {
+                // savour the hit for a moment
+                self.hit_stop = self.hit_stop.saturating_sub(delta_t);
+            } else {
+                self.game_tick(control_state, delta_t);
+            }
         }
     }
 
@@ -60,13 +130,68 @@ impl LogicState {
         Box::new(i)
     }
 
-    pub fn bounce(&self) -> Option<Bounce> {
-        self.bounce
+    /// What the ball's bounced off since the events were last cleared, oldest first
+    pub fn impacts(&self) -> &[Impact] {
+        &self.impacts
     }
 
-    fn game_tick(&mut self, control_state: &ControlState, now: Instant, delta_t: Duration) {
-        // upkeep
-        self.bounce = None;
+    pub fn ball(&self) -> &GameObject {
+        &self.ball
+    }
+
+    /// Once the bottom of the ball's below this, the paddle can't save it any more
+    pub fn too_late_y(&self) -> f32 {
+        self.paddle.y + self.paddle.height / 2.0
+    }
+
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    /// The bricks the ball's broken since the events were last cleared
+    pub fn destroyed_bricks(&self) -> &[GameObject] {
+        &self.destroyed_bricks
+    }
+
+    /// Whether the ball's gone out the bottom since the events were last cleared
+    pub fn ball_lost(&self) -> bool {
+        self.ball_lost
+    }
+
+    /// Forget what's happened, once everything that cares has had a look. Until then events
+    /// pile up, however many ticks that takes.
+    pub fn clear_events(&mut self) {
+        self.impacts.clear();
+        self.destroyed_bricks.clear();
+        self.ball_lost = false;
+    }
+
+    pub fn phase(&self) -> Phase {
+        if self.game_over {
+            Phase::GameOver
+        } else if self.game_started.is_some() {
+            Phase::Playing
+        } else {
+            Phase::Menu
+        }
+    }
+
+    /// What the game's actually being played with, e.g. the seed picked if none was given
+    pub fn settings(&self) -> &LogicSettings {
+        &self.settings
+    }
+
+    pub fn level(&self) -> u32 {
+        self.settings.level
+    }
+
+    /// Is the game paused? Nothing moves and no time passes while it is.
+    pub fn paused(&self) -> bool {
+        self.paused
+    }
+
+    fn game_tick(&mut self, control_state: &ControlState, delta_t: Duration) {
+        self.time_played += delta_t;
 
         // move paddle to mouse
         let new_paddle_x = (control_state.mouse_coords().0).clamp(
@@ -88,34 +213,43 @@ impl LogicState {
             self.ball.x_v *= -1.0;
             self.ball.x -= self.ball.x + self.ball.width / 2.0 - 1.0;
 
-            self.bounce = Some(Bounce::PlayfieldBorder);
+            self.impacts.push(self.ball_impact(Bounce::PlayfieldBorder));
         }
         if self.ball.x - self.ball.width / 2.0 < -1.0 {
             // left border
             self.ball.x_v *= -1.0;
             self.ball.x -= self.ball.x - self.ball.width / 2.0 + 1.0;
 
-            self.bounce = Some(Bounce::PlayfieldBorder);
+            self.impacts.push(self.ball_impact(Bounce::PlayfieldBorder));
         }
         if self.ball.y + self.ball.height / 2.0 > 1.0 {
             // top border
             self.ball.y_v *= -1.0;
             self.ball.y -= self.ball.y + self.ball.height / 2.0 - 1.0;
 
-            self.bounce = Some(Bounce::PlayfieldBorder);
+            self.impacts.push(self.ball_impact(Bounce::PlayfieldBorder));
         }
         if self.ball.y - self.ball.height / 2.0 < -1.0 {
             // bottom border
-            if self.balls_remaining > 0 {
-                self.balls_remaining -= 1;
-                self.ball = GameObject::ball(self.balls_remaining);
+            self.ball_lost = true;
+            if self.settings.ruleset == Ruleset::Practice || self.balls_remaining > 0 {
+                if self.settings.ruleset == Ruleset::Classic {
+                    self.balls_remaining -= 1;
+                }
+                self.ball = GameObject::ball(
+                    self.balls_remaining,
+                    launch_jitter(&mut self.rng),
+                    ball_speed(&self.settings),
+                    &self.settings,
+                );
                 self.too_late = false;
-                println!(
+                console::say(format!(
                     "balls remaining: {}, score: {}",
                     self.balls_remaining, self.score
-                );
+                ));
             } else {
-                println!("game over! score: {}", self.score);
+                console::say(format!("game over! score: {}", self.score));
+                self.game_over = true;
                 self.ball.x = 0.0;
                 self.ball.y = -0.5;
                 self.ball.x_v = 0.0;
@@ -130,10 +264,10 @@ impl LogicState {
             self.ball.y -=
                 self.ball.y - self.ball.height / 2.0 - self.paddle.y - self.paddle.height / 2.0;
 
-            self.bounce = Some(Bounce::Paddle);
+            self.impacts.push(self.ball_impact(Bounce::Paddle));
         }
 
-        if self.ball.y - self.ball.height / 2.0 < self.paddle.y + self.paddle.height / 2.0 {
+        if self.ball.y - self.ball.height / 2.0 < self.too_late_y() {
             self.too_late = true;
         }
 
@@ -157,22 +291,31 @@ impl LogicState {
                     self.ball.x_v *= -1.0;
                 }
 
-                self.bounce = Some(Bounce::Brick);
+                self.impacts.push(Impact {
+                    bounce: Bounce::Brick,
+                    x: brick.x,
+                    y: brick.y,
+                });
 
                 // update score
-                self.score += 1 * time_elapsed_to_score_mult(
-                    now.duration_since(
-                        self.game_started
-                            .expect("game logic running, but game hasn't started? exiting"),
-                    ),
-                );
+                self.score += 1 * time_elapsed_to_score_mult(self.time_played);
 
                 // destroy the brick
-                self.bricks.remove(index);
+                let brick = self.bricks.remove(index);
+                self.destroyed_bricks.push(brick);
+                self.hit_stop = Duration::from_secs_f32(self.settings.hit_stop);
                 break;
             }
         }
     }
+
+    fn ball_impact(&self, bounce: Bounce) -> Impact {
+        Impact {
+            bounce,
+            x: self.ball.x,
+            y: self.ball.y,
+        }
+    }
 }
 
 fn objs_overlap(a: &GameObject, b: &GameObject) -> bool {
@@ -182,6 +325,18 @@ fn objs_overlap(a: &GameObject, b: &GameObject) -> bool {
         && a.y + a.height / 2.0 > b.y - b.height / 2.0
 }
 
+fn launch_jitter(rng: &mut Rng) -> f32 {
+    (rng.next_f32() * 2.0 - 1.0) * game_objs::BALL_LAUNCH_JITTER
+}
+
+fn ball_speed(settings: &LogicSettings) -> f32 {
+    settings.ball_speed * level_to_speed_mult(settings.level)
+}
+
+fn level_to_speed_mult(level: u32) -> f32 {
+    1.0 + 0.1 * level.saturating_sub(1) as f32
+}
+
 fn time_elapsed_to_score_mult(elapsed: Duration) -> u32 {
     if elapsed < Duration::from_secs(10) {
         10
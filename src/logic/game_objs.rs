@@ -1,5 +1,8 @@
-use crate::view::video::Drawable;
+use crate::settings::LogicSettings;
 use crate::view::video::Vertex;
+use crate::view::video::{atlas::Atlas, Drawable, TexturedVertex};
+
+pub const BALLS: u32 = 2;
 
 pub const PADDLE_WIDTH: f32 = 0.25;
 pub const PADDLE_HEIGHT: f32 = 0.025;
@@ -10,6 +13,9 @@ pub const PADDLE_PUSH_SCALE: f32 = 600.0;
 pub const BALL_WIDTH: f32 = 0.025;
 pub const BALL_HEIGHT: f32 = 0.025;
 pub const BALL_COLOR: [f32; 3] = [0.259, 0.051, 0.671];
+pub const BALL_SPEED: f32 = 1.0;
+/// How far off the usual launch angle a ball may start, in degrees
+pub const BALL_LAUNCH_JITTER: f32 = 15.0;
 
 pub const PLAYFIELD_COLOR: [f32; 3] = [1.0, 1.0, 1.0];
 
@@ -45,6 +51,17 @@ pub struct GameObject {
     pub kind: GameObjectKind,
 }
 impl GameObject {
+    /// Which sprite in the atlas we're drawn with, if any
+    pub fn sprite_name(&self) -> Option<&'static str> {
+        match self.kind {
+            GameObjectKind::Paddle => Some("paddle"),
+            GameObjectKind::Brick(_) => Some("brick"),
+            GameObjectKind::Ball => Some("ball"),
+            // always drawn flat, underneath everything else
+            GameObjectKind::Playfield => None,
+        }
+    }
+
     pub fn new(
         x: f32,
         y: f32,
@@ -67,20 +84,25 @@ impl GameObject {
         }
     }
 
-    pub fn paddle() -> GameObject {
+    pub fn paddle(settings: &LogicSettings) -> GameObject {
         Self::new(
             0.0,
             PADDLE_VERTICAL_OFFSET,
-            PADDLE_WIDTH,
-            PADDLE_HEIGHT,
+            settings.paddle_width,
+            settings.paddle_height,
             0.0,
             0.0,
-            crate::view::video::iso_tri_down(PADDLE_WIDTH, PADDLE_HEIGHT, PADDLE_COLOR).to_vec(),
+            crate::view::video::iso_tri_down(
+                settings.paddle_width,
+                settings.paddle_height,
+                settings.paddle_color,
+            )
+            .to_vec(),
             GameObjectKind::Paddle,
         )
     }
 
-    pub fn playfield() -> GameObject {
+    pub fn playfield(settings: &LogicSettings) -> GameObject {
         Self::new(
             0.0,
             0.0,
@@ -88,46 +110,58 @@ impl GameObject {
             2.0,
             0.0,
             0.0,
-            crate::view::video::quad(2.0, 2.0, PLAYFIELD_COLOR).to_vec(),
+            crate::view::video::quad(2.0, 2.0, settings.playfield_color).to_vec(),
             GameObjectKind::Playfield,
         )
     }
 
-    pub fn ball(num: u32) -> GameObject {
-        let start_angle = if num % 2 == 0 { 290_f32 } else { 250_f32 };
+    /// `jitter` is added to the launch angle, in degrees, and `speed` scales the velocity
+    pub fn ball(num: u32, jitter: f32, speed: f32, settings: &LogicSettings) -> GameObject {
+        let start_angle = if num % 2 == 0 { 290_f32 } else { 250_f32 } + jitter;
         Self::new(
             0.0,
             -0.25,
-            BALL_WIDTH,
-            BALL_HEIGHT,
-            f32::cos(start_angle.to_radians()),
-            f32::sin(start_angle.to_radians()),
-            crate::view::video::quad(0.025, BALL_HEIGHT, BALL_COLOR).to_vec(),
+            settings.ball_width,
+            settings.ball_height,
+            speed * f32::cos(start_angle.to_radians()),
+            speed * f32::sin(start_angle.to_radians()),
+            crate::view::video::quad(
+                settings.ball_width,
+                settings.ball_height,
+                settings.ball_color,
+            )
+            .to_vec(),
             GameObjectKind::Ball,
         )
     }
 
-    pub fn brick(x: f32, y: f32, color: [f32; 3]) -> GameObject {
+    pub fn brick(x: f32, y: f32, width: f32, height: f32, color: [f32; 3]) -> GameObject {
         Self::new(
             x,
             y,
-            BRICK_WIDTH,
-            BRICK_HEIGHT,
+            width,
+            height,
             0.0,
             0.0,
-            crate::view::video::quad(BRICK_WIDTH, BRICK_HEIGHT, color).to_vec(),
+            crate::view::video::quad(width, height, color).to_vec(),
             GameObjectKind::Brick(1),
         )
     }
 
-    pub fn bricks() -> Vec<GameObject> {
+    pub fn bricks(settings: &LogicSettings) -> Vec<GameObject> {
         let mut bricks: Vec<GameObject> = Vec::new();
-        for i in 0..BRICK_COLUMNS {
-            let x = ((i as f32 + 0.5) * 2.0 / BRICK_COLUMNS as f32) - 1.0;
-            for j in 0..BRICK_ROWS {
+        for i in 0..settings.brick_columns {
+            let x = ((i as f32 + 0.5) * 2.0 / settings.brick_columns as f32) - 1.0;
+            for j in 0..settings.brick_rows {
                 // y coords are top half only
-                let y = (j as f32 + 0.5) / BRICK_ROWS as f32;
-                bricks.push(Self::brick(x, y, BRICK_COLOR));
+                let y = (j as f32 + 0.5) / settings.brick_rows as f32;
+                bricks.push(Self::brick(
+                    x,
+                    y,
+                    settings.brick_width,
+                    settings.brick_height,
+                    settings.brick_color,
+                ));
             }
         }
         bricks
@@ -142,4 +176,14 @@ impl Drawable for GameObject {
         }
         vertices
     }
+
+    fn get_textured_vertices(&self, atlas: &Atlas) -> Option<Vec<TexturedVertex>> {
+        let sprite = atlas.sprite(self.sprite_name()?)?;
+        let mut vertices = crate::view::video::textured_quad(self.width, self.height, sprite);
+        for vertex in vertices.iter_mut() {
+            vertex.position[0] += self.x;
+            vertex.position[1] += self.y;
+        }
+        Some(vertices.to_vec())
+    }
 }
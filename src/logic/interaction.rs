@@ -4,3 +4,12 @@ pub enum Bounce {
     PlayfieldBorder,
     Brick,
 }
+
+/// A bounce, and where it happened
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Impact {
+    pub bounce: Bounce,
+    /// Where the ball was, or the brick's center if it hit one
+    pub x: f32,
+    pub y: f32,
+}
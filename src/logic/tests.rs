@@ -0,0 +1,47 @@
+use std::time::{Duration, Instant};
+
+use crate::control::ControlState;
+use crate::logic::{time_elapsed_to_score_mult, LogicState, Phase};
+use crate::replay::Tick;
+use crate::settings::Settings;
+
+const STEP: Duration = Duration::from_millis(10);
+
+fn tick(clicked: bool, paused: bool) -> Tick {
+    Tick {
+        delta_t: STEP,
+        mouse_x: 0.0,
+        mouse_y: 0.0,
+        clicked,
+        paused,
+    }
+}
+
+#[test]
+fn no_time_is_played_while_paused() {
+    let settings = Settings::default();
+    let mut logic_state = LogicState::new(&settings);
+    let mut control_state = ControlState::new(&settings);
+    let start = Instant::now();
+
+    control_state.replay(&tick(true, false));
+    logic_state.update(&control_state, start, STEP);
+    assert_eq!(logic_state.phase(), Phase::Playing);
+    assert_eq!(logic_state.time_played, STEP);
+
+    // long enough to drop the score multiplier, if it counted
+    control_state.replay(&tick(false, true));
+    let ball = logic_state.ball().clone();
+    for _ in 0..2000 {
+        logic_state.update(&control_state, start, STEP);
+    }
+    assert!(logic_state.paused());
+    assert_eq!(logic_state.time_played, STEP);
+    assert_eq!(logic_state.ball(), &ball);
+    assert_eq!(time_elapsed_to_score_mult(logic_state.time_played), 10);
+
+    control_state.replay(&tick(false, false));
+    logic_state.update(&control_state, start, STEP);
+    assert!(!logic_state.paused());
+    assert_eq!(logic_state.time_played, STEP * 2);
+}
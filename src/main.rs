@@ -1,22 +1,82 @@
+use std::path::{Path, PathBuf};
+use std::process;
 use std::time::{Duration, Instant};
 
+use crossterm::event as terminal_event;
 use winit::event::{Event, WindowEvent};
 use winit::event_loop::{ControlFlow, EventLoopBuilder};
 
 use breakout::control::ControlState;
+use breakout::export;
 use breakout::logic::LogicState;
+use breakout::pacing::{FixedStep, FrameLimiter};
+use breakout::replay::{Replay, Tick};
+use breakout::settings::{self, Args, Settings, VideoBackend};
+use breakout::stats::FrameStats;
+use breakout::view::video::screenshot::ScreenshotError;
 use breakout::view::ViewState;
 
+/// Frame rate when playing in the terminal, anything more is wasted on most connections
+const TERMINAL_FRAME_TIME: Duration = Duration::from_millis(1000 / 30);
+
 fn main() {
+    // settings
+    let args = Args::parse(std::env::args().skip(1)).unwrap_or_else(|e| {
+        eprintln!("{}\n\n{}", e, settings::USAGE);
+        process::exit(2);
+    });
+    if args.help {
+        print!("{}", settings::USAGE);
+        return;
+    }
+    let settings = Settings::load(&args).unwrap_or_else(|e| {
+        eprintln!("{}, exiting", e);
+        process::exit(1);
+    });
+
+    if let Some(path) = &args.export {
+        run_export(path, &settings);
+        return;
+    }
+
+    match settings.video.backend {
+        VideoBackend::Window => run_window(&settings, args.record),
+        VideoBackend::Terminal => run_terminal(&settings, args.record),
+    }
+}
+
+fn run_export(path: &Path, settings: &Settings) {
+    let replay = Replay::load(path).unwrap_or_else(|e| {
+        eprintln!("{}, exiting", e);
+        process::exit(1);
+    });
+    let exported = export::export(&replay, settings).unwrap_or_else(|e| {
+        eprintln!("{}, exiting", e);
+        process::exit(1);
+    });
+    println!(
+        "exported {} frames ({:.1} secs) to {}",
+        exported.frames,
+        exported.duration.as_secs_f32(),
+        settings.export.dir.display()
+    );
+}
+
+fn run_window(settings: &Settings, record: Option<PathBuf>) {
     // create event loop
     let event_loop = EventLoopBuilder::new()
         .build()
         .expect("unable to create window, exiting");
     event_loop.set_control_flow(ControlFlow::Poll);
 
-    let mut logic_state = LogicState::new();
-    let mut view_state = ViewState::new(&event_loop);
-    let mut control_state = ControlState::new();
+    let mut logic_state = LogicState::new(settings);
+    let mut view_state = ViewState::new(&event_loop, settings);
+    let mut control_state = ControlState::new(settings);
+    let mut recording = record.map(|path| (path, Replay::new(logic_state.settings().clone())));
+
+    let frame_stats = settings.video.frame_stats.clone();
+    let mut fixed_step = FixedStep::new(settings.logic.tick_rate);
+    let mut frame_limiter = FrameLimiter::new(settings.video.max_fps);
 
     let init_time = Instant::now();
     let mut last_frame_was: Option<Instant> = None;
@@ -27,14 +87,22 @@ fn main() {
                 event: WindowEvent::CloseRequested,
                 ..
             } => {
-                let time_elapsed = Instant::now().duration_since(init_time).as_secs_f32();
-                println!("frame_count: {}", view_state.frame_count());
-                println!("time_elapsed: {} secs", time_elapsed);
-                println!("frames per second: {}", view_state.calculate_fps());
+                print!("{}", report(&view_state, init_time));
+                save_volume(&control_state);
+                save_replay(&recording);
+                save_frame_stats(view_state.frame_stats(), frame_stats.as_deref());
 
                 window_target.exit();
             }
             Event::AboutToWait => {
+                // sleep through most of the wait so events still get handled
+                if let Some(wake) = frame_limiter.sleep_until(Instant::now()) {
+                    window_target.set_control_flow(ControlFlow::WaitUntil(wake));
+                    return;
+                }
+                window_target.set_control_flow(ControlFlow::Poll);
+                frame_limiter.wait();
+
                 // timey-wimey
                 let now = Instant::now();
                 let delta_t = match last_frame_was {
@@ -42,9 +110,17 @@ fn main() {
                     None => Duration::ZERO,
                 };
 
-                logic_state.update(&control_state, now, delta_t);
-
-                view_state.update(&logic_state);
+                let logic_time = tick(
+                    &mut logic_state,
+                    &control_state,
+                    &mut recording,
+                    &mut fixed_step,
+                    now,
+                    delta_t,
+                );
+                view_state.update(&logic_state, &control_state, logic_time);
+                logic_state.clear_events();
+                report_screenshots(view_state.take_screenshots());
 
                 // more timey-wimey
                 last_frame_was = Some(now);
@@ -53,3 +129,141 @@ fn main() {
         })
         .expect("unable to run event loop, exiting");
 }
+
+fn run_terminal(settings: &Settings, record: Option<PathBuf>) {
+    let mut logic_state = LogicState::new(settings);
+    let mut view_state = ViewState::terminal(settings).unwrap_or_else(|e| {
+        eprintln!("unable to use the terminal: {}, exiting", e);
+        process::exit(1);
+    });
+    let mut control_state = ControlState::new(settings);
+    let mut recording = record.map(|path| (path, Replay::new(logic_state.settings().clone())));
+
+    let mut fixed_step = FixedStep::new(settings.logic.tick_rate);
+    let frame_time = match settings.video.max_fps {
+        0 => TERMINAL_FRAME_TIME,
+        max_fps => Duration::from_secs(1) / max_fps,
+    };
+
+    let init_time = Instant::now();
+    let mut last_frame_was: Option<Instant> = None;
+
+    while !control_state.quit_requested() {
+        // timey-wimey
+        let now = Instant::now();
+        let delta_t = match last_frame_was {
+            Some(then) => now.duration_since(then),
+            None => Duration::ZERO,
+        };
+
+        let logic_time = tick(
+            &mut logic_state,
+            &control_state,
+            &mut recording,
+            &mut fixed_step,
+            now,
+            delta_t,
+        );
+        view_state.update(&logic_state, &control_state, logic_time);
+        logic_state.clear_events();
+
+        // more timey-wimey
+        last_frame_was = Some(now);
+
+        // handle input until it's time for the next frame
+        let next_frame = now + frame_time;
+        loop {
+            let timeout = next_frame.saturating_duration_since(Instant::now());
+            if !terminal_event::poll(timeout).expect("unable to read the terminal, exiting") {
+                break;
+            }
+            let event = terminal_event::read().expect("unable to read the terminal, exiting");
+            control_state.update_terminal(&view_state, event);
+        }
+    }
+
+    // the terminal has to be given back before anything's printed
+    let report = report(&view_state, init_time);
+    let frame_stats = view_state.frame_stats().clone();
+    let screenshots = view_state.take_screenshots();
+    drop(view_state);
+    report_screenshots(screenshots);
+    print!("{}", report);
+    save_volume(&control_state);
+    save_replay(&recording);
+    save_frame_stats(&frame_stats, settings.video.frame_stats.as_deref());
+}
+
+/// Run however many logic ticks are due for a frame, recording each one, and return how long
+/// they took
+fn tick(
+    logic_state: &mut LogicState,
+    control_state: &ControlState,
+    recording: &mut Option<(PathBuf, Replay)>,
+    fixed_step: &mut FixedStep,
+    now: Instant,
+    delta_t: Duration,
+) -> Duration {
+    let start = Instant::now();
+    let step = fixed_step.step();
+    for _ in 0..fixed_step.advance(delta_t) {
+        logic_state.update(control_state, now, step);
+        if let Some((_, replay)) = recording {
+            replay.record(Tick::new(step, control_state));
+        }
+    }
+    start.elapsed()
+}
+
+/// Stats for the session, printed on the way out
+fn report(view_state: &ViewState, init_time: Instant) -> String {
+    let time_elapsed = Instant::now().duration_since(init_time).as_secs_f32();
+    format!(
+        "frame_count: {}\ntime_elapsed: {} secs\naverage draw time: {:.3} ms ({})\n{}",
+        view_state.frame_count(),
+        time_elapsed,
+        view_state.average_draw_time().as_secs_f64() * 1000.0,
+        if view_state.persistent_buffers() {
+            "persistent buffers"
+        } else {
+            "rebuilt every frame"
+        },
+        view_state.frame_stats().summary()
+    )
+}
+
+fn report_screenshots(screenshots: Vec<Result<PathBuf, ScreenshotError>>) {
+    for saved in screenshots {
+        match saved {
+            Ok(path) => println!("saved screenshot to {}", path.display()),
+            Err(e) => eprintln!("warning: {}", e),
+        }
+    }
+}
+
+fn save_replay(recording: &Option<(PathBuf, Replay)>) {
+    let Some((path, replay)) = recording else {
+        return;
+    };
+    match replay.save(path) {
+        Ok(()) => println!("saved replay to {}", path.display()),
+        Err(e) => eprintln!("warning: {}", e),
+    }
+}
+
+fn save_frame_stats(frame_stats: &FrameStats, path: Option<&Path>) {
+    let Some(path) = path else {
+        return;
+    };
+    match frame_stats.save(path) {
+        Ok(()) => println!("saved frame stats to {}", path.display()),
+        Err(e) => eprintln!("warning: {}", e),
+    }
+}
+
+fn save_volume(control_state: &ControlState) {
+    if let Err(e) = settings::save_volume(&control_state.initial_volume(), &control_state.volume())
+    {
+        eprintln!("warning: {}", e);
+    }
+}
@@ -0,0 +1,98 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[cfg(test)]
+mod tests;
+
+/// Default logic ticks per second, however fast frames are drawn
+pub const TICK_RATE: u32 = 120;
+/// Most ticks run to catch up before a frame, past this the game slows down instead of
+/// spending ever longer catching up
+pub const MAX_TICKS_PER_FRAME: u32 = 8;
+/// How long before a frame's due to stop sleeping and spin instead, since sleeps overshoot
+pub const SPIN_TIME: Duration = Duration::from_millis(2);
+
+/// Splits time up into equal logic ticks, carrying whatever's left over to the next frame
+#[derive(Clone, Debug)]
+pub struct FixedStep {
+    step: Duration,
+    accumulated: Duration,
+}
+impl FixedStep {
+    pub fn new(tick_rate: u32) -> FixedStep {
+        FixedStep {
+            step: Duration::from_secs(1) / tick_rate.max(1),
+            accumulated: Duration::ZERO,
+        }
+    }
+
+    /// How long each tick is
+    pub fn step(&self) -> Duration {
+        self.step
+    }
+
+    /// How many ticks are due now `elapsed` more time has gone by
+    pub fn advance(&mut self, elapsed: Duration) -> u32 {
+        self.accumulated += elapsed;
+        let ticks = (self.accumulated.as_nanos() / self.step.as_nanos()) as u32;
+        if ticks > MAX_TICKS_PER_FRAME {
+            // too far behind to catch up, drop the rest
+            self.accumulated = Duration::ZERO;
+            return MAX_TICKS_PER_FRAME;
+        }
+        self.accumulated -= self.step * ticks;
+        ticks
+    }
+}
+
+/// Keeps frames from coming faster than a cap, sleeping while there's time to spare and
+/// spinning for the last stretch
+#[derive(Clone, Debug)]
+pub struct FrameLimiter {
+    /// `None` if uncapped
+    frame_time: Option<Duration>,
+    next_frame: Option<Instant>,
+}
+impl FrameLimiter {
+    /// `max_fps` of 0 means no cap
+    pub fn new(max_fps: u32) -> FrameLimiter {
+        FrameLimiter {
+            frame_time: (max_fps > 0).then(|| Duration::from_secs(1) / max_fps),
+            next_frame: None,
+        }
+    }
+
+    /// When to stop sleeping and start spinning for the next frame, or `None` if there's no
+    /// time to sleep
+    pub fn sleep_until(&self, now: Instant) -> Option<Instant> {
+        let wake = self.next_frame?.checked_sub(SPIN_TIME)?;
+        (wake > now).then_some(wake)
+    }
+
+    /// Wait out whatever's left until the next frame's due, then schedule the one after
+    pub fn wait(&mut self) {
+        if let Some(wake) = self.sleep_until(Instant::now()) {
+            thread::sleep(wake.saturating_duration_since(Instant::now()));
+        }
+        if let Some(next_frame) = self.next_frame {
+            while Instant::now() < next_frame {
+                std::hint::spin_loop();
+            }
+        }
+        self.schedule(Instant::now());
+    }
+
+    /// Frames are due at a steady rate from one to the next, unless one's more than a frame
+    /// late, then they start afresh from `now` rather than rushing to catch up
+    fn schedule(&mut self, now: Instant) {
+        let Some(frame_time) = self.frame_time else {
+            return;
+        };
+        self.next_frame = Some(match self.next_frame {
+            Some(next_frame) if now.saturating_duration_since(next_frame) < frame_time => {
+                next_frame + frame_time
+            }
+            _ => now + frame_time,
+        });
+    }
+}
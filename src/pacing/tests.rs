@@ -0,0 +1,59 @@
+use crate::pacing::{FixedStep, FrameLimiter, MAX_TICKS_PER_FRAME, SPIN_TIME};
+use std::time::{Duration, Instant};
+
+#[test]
+fn ticks_carry_over_between_frames() {
+    let mut fixed_step = FixedStep::new(100);
+    assert_eq!(fixed_step.step(), Duration::from_millis(10));
+
+    assert_eq!(fixed_step.advance(Duration::ZERO), 0);
+    assert_eq!(fixed_step.advance(Duration::from_millis(15)), 1);
+    assert_eq!(fixed_step.advance(Duration::from_millis(4)), 0);
+    // 5ms left over, plus 6
+    assert_eq!(fixed_step.advance(Duration::from_millis(6)), 1);
+    assert_eq!(fixed_step.advance(Duration::from_millis(29)), 3);
+
+    // a long stall isn't caught up on all at once
+    assert_eq!(
+        fixed_step.advance(Duration::from_secs(1)),
+        MAX_TICKS_PER_FRAME
+    );
+    assert_eq!(fixed_step.advance(Duration::from_millis(9)), 0);
+}
+
+#[test]
+fn frames_are_held_to_the_cap() {
+    let start = Instant::now();
+    let mut limiter = FrameLimiter::new(100);
+    assert_eq!(limiter.sleep_until(start), None);
+
+    limiter.schedule(start);
+    let next_frame = start + Duration::from_millis(10);
+    assert_eq!(limiter.sleep_until(start), Some(next_frame - SPIN_TIME));
+    // too close to sleep
+    assert_eq!(limiter.sleep_until(next_frame - SPIN_TIME / 2), None);
+
+    // a little late still keeps to the schedule
+    limiter.schedule(next_frame + Duration::from_millis(3));
+    assert_eq!(limiter.next_frame, Some(start + Duration::from_millis(20)));
+    // a lot late starts over
+    let late = start + Duration::from_millis(50);
+    limiter.schedule(late);
+    assert_eq!(limiter.next_frame, Some(late + Duration::from_millis(10)));
+
+    let before = Instant::now();
+    let mut limiter = FrameLimiter::new(200);
+    for _ in 0..4 {
+        limiter.wait();
+    }
+    // the first frame's not held back
+    assert!(before.elapsed() >= Duration::from_millis(15));
+}
+
+#[test]
+fn uncapped_frames_never_wait() {
+    let mut limiter = FrameLimiter::new(0);
+    limiter.wait();
+    assert_eq!(limiter.next_frame, None);
+    assert_eq!(limiter.sleep_until(Instant::now()), None);
+}
@@ -0,0 +1,192 @@
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::control::ControlState;
+use crate::settings::LogicSettings;
+
+#[cfg(test)]
+mod tests;
+
+/// Bumped whenever old replays would play back differently
+pub const REPLAY_VERSION: u32 = 1;
+
+/// Everything the game was told during one logic update
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Tick {
+    pub delta_t: Duration,
+    pub mouse_x: f32,
+    pub mouse_y: f32,
+    pub clicked: bool,
+    pub paused: bool,
+}
+impl Tick {
+    pub fn new(delta_t: Duration, control_state: &ControlState) -> Tick {
+        let (mouse_x, mouse_y) = control_state.mouse_coords();
+        Tick {
+            delta_t,
+            mouse_x,
+            mouse_y,
+            clicked: control_state.clicked(),
+            paused: control_state.paused(),
+        }
+    }
+
+    /// e.g. `16666667 0.25 -0.5 cp`, with nanoseconds, mouse coordinates, then whether it was
+    /// clicked and paused, or `-` for neither
+    fn to_line(self) -> String {
+        let mut flags = String::new();
+        if self.clicked {
+            flags.push('c');
+        }
+        if self.paused {
+            flags.push('p');
+        }
+        if flags.is_empty() {
+            flags.push('-');
+        }
+        format!(
+            "{} {} {} {}",
+            self.delta_t.as_nanos(),
+            self.mouse_x,
+            self.mouse_y,
+            flags
+        )
+    }
+
+    fn from_line(line: &str) -> Option<Tick> {
+        let mut fields = line.split_whitespace();
+        let tick = Tick {
+            delta_t: Duration::from_nanos(fields.next()?.parse().ok()?),
+            mouse_x: fields.next()?.parse().ok()?,
+            mouse_y: fields.next()?.parse().ok()?,
+            clicked: false,
+            paused: false,
+        };
+        let flags = fields.next()?;
+        if fields.next().is_some() || !flags.chars().all(|flag| "cp-".contains(flag)) {
+            return None;
+        }
+        Some(Tick {
+            clicked: flags.contains('c'),
+            paused: flags.contains('p'),
+            ..tick
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(PathBuf, io::Error),
+    Write(PathBuf, io::Error),
+    Parse(PathBuf, toml::de::Error),
+    Serialize(toml::ser::Error),
+    Version(PathBuf, u32),
+    Tick(PathBuf, usize),
+}
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Io(path, e) => write!(f, "unable to read {}: {}", path.display(), e),
+            ReplayError::Write(path, e) => write!(f, "unable to write {}: {}", path.display(), e),
+            ReplayError::Parse(path, e) => write!(f, "unable to parse {}: {}", path.display(), e),
+            ReplayError::Serialize(e) => write!(f, "unable to serialize replay: {}", e),
+            ReplayError::Version(path, version) => write!(
+                f,
+                "{} is a version {} replay, only version {} can be played",
+                path.display(),
+                version,
+                REPLAY_VERSION
+            ),
+            ReplayError::Tick(path, line) => {
+                write!(f, "{}: tick {} isn't valid", path.display(), line)
+            }
+        }
+    }
+}
+impl Error for ReplayError {}
+
+/// How a replay's stored on disk, ticks one to a line so they stay compact
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ReplayFile {
+    version: u32,
+    ticks: String,
+    logic: LogicSettings,
+}
+
+/// A whole game's input, enough to play it back exactly
+#[derive(Clone, Debug, PartialEq)]
+pub struct Replay {
+    /// Including the seed, so the ball launches the same way
+    pub logic: LogicSettings,
+    pub ticks: Vec<Tick>,
+}
+impl Replay {
+    pub fn new(logic: LogicSettings) -> Replay {
+        Replay {
+            logic,
+            ticks: Vec::new(),
+        }
+    }
+
+    pub fn record(&mut self, tick: Tick) {
+        self.ticks.push(tick);
+    }
+
+    /// Total time covered, pauses included
+    pub fn duration(&self) -> Duration {
+        self.ticks.iter().map(|tick| tick.delta_t).sum()
+    }
+
+    pub fn load(path: &Path) -> Result<Replay, ReplayError> {
+        let text = fs::read_to_string(path).map_err(|e| ReplayError::Io(path.to_owned(), e))?;
+        Replay::from_toml(&text, path)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), ReplayError> {
+        fs::write(path, self.to_toml()?).map_err(|e| ReplayError::Write(path.to_owned(), e))
+    }
+
+    pub fn to_toml(&self) -> Result<String, ReplayError> {
+        let mut ticks = String::new();
+        for tick in self.ticks.iter() {
+            ticks.push_str(&tick.to_line());
+            ticks.push('\n');
+        }
+        toml::to_string(&ReplayFile {
+            version: REPLAY_VERSION,
+            ticks,
+            logic: self.logic.clone(),
+        })
+        .map_err(ReplayError::Serialize)
+    }
+
+    /// `path` is only for errors
+    pub fn from_toml(text: &str, path: &Path) -> Result<Replay, ReplayError> {
+        let file: ReplayFile =
+            toml::from_str(text).map_err(|e| ReplayError::Parse(path.to_owned(), e))?;
+        if file.version != REPLAY_VERSION {
+            return Err(ReplayError::Version(path.to_owned(), file.version));
+        }
+
+        let ticks = file
+            .ticks
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(index, line)| {
+                Tick::from_line(line).ok_or_else(|| ReplayError::Tick(path.to_owned(), index + 1))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Replay {
+            logic: file.logic,
+            ticks,
+        })
+    }
+}
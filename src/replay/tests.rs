@@ -0,0 +1,95 @@
+use crate::control::ControlState;
+use crate::logic::game_objs::GameObject;
+use crate::logic::LogicState;
+use crate::replay::{Replay, ReplayError, Tick};
+use crate::settings::{LogicSettings, Settings};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// A click to start, then the paddle swept back and forth, with a pause in the middle
+fn sweeping_replay() -> Replay {
+    let mut replay = Replay::new(LogicSettings {
+        seed: Some(7),
+        ..LogicSettings::default()
+    });
+    for index in 0..900 {
+        replay.record(Tick {
+            delta_t: if index == 0 {
+                Duration::ZERO
+            } else {
+                Duration::from_micros(16_667 + index % 3 * 1_000)
+            },
+            mouse_x: (index as f32 / 40.0).sin() * 0.9,
+            mouse_y: -0.5,
+            clicked: true,
+            paused: (400..430).contains(&index),
+        });
+    }
+    replay
+}
+
+fn play(replay: &Replay) -> Vec<GameObject> {
+    let settings = Settings {
+        logic: replay.logic.clone(),
+        ..Settings::default()
+    };
+    let mut logic_state = LogicState::new(&settings);
+    let mut control_state = ControlState::new(&settings);
+
+    let start = Instant::now();
+    let mut time = Duration::ZERO;
+    for tick in replay.ticks.iter() {
+        time += tick.delta_t;
+        control_state.replay(tick);
+        logic_state.update(&control_state, start + time, tick.delta_t);
+        logic_state.clear_events();
+    }
+    logic_state.game_objs().cloned().collect()
+}
+
+#[test]
+fn replays_survive_a_round_trip() {
+    let replay = sweeping_replay();
+    let text = replay.to_toml().unwrap();
+    assert!(text.contains("version = 1"));
+    assert_eq!(Replay::from_toml(&text, Path::new("test")).unwrap(), replay);
+}
+
+#[test]
+fn bad_replays_are_rejected() {
+    let mut text = sweeping_replay().to_toml().unwrap();
+    assert!(matches!(
+        Replay::from_toml(
+            &text.replace("version = 1", "version = 2"),
+            Path::new("test")
+        ),
+        Err(ReplayError::Version(_, 2))
+    ));
+
+    text = text.replacen(" -0.5 c", " -0.5 x", 1);
+    assert!(matches!(
+        Replay::from_toml(&text, Path::new("test")),
+        Err(ReplayError::Tick(_, 1))
+    ));
+}
+
+#[test]
+fn replays_play_out_the_same() {
+    let replay = sweeping_replay();
+    let loaded = Replay::from_toml(&replay.to_toml().unwrap(), Path::new("test")).unwrap();
+
+    let game_objs = play(&replay);
+    assert_eq!(play(&loaded), game_objs);
+    // the game actually went somewhere
+    let fresh = play(&Replay {
+        ticks: replay.ticks[..1].to_vec(),
+        ..replay.clone()
+    });
+    assert_ne!(fresh, game_objs);
+}
+
+#[test]
+fn the_seed_is_remembered() {
+    let logic_state = LogicState::new(&Settings::default());
+    assert!(logic_state.settings().seed.is_some());
+}
@@ -0,0 +1,1017 @@
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::logic::game_objs;
+use crate::view::audio::synth::SynthParams;
+use crate::view::audio::Volume;
+use crate::view::{audio, video};
+
+#[cfg(test)]
+mod tests;
+
+pub const USAGE: &str = "\
+usage: breakout [options]
+
+options:
+    --config <path>       read settings from <path> instead of the default config file
+    --level <n>           start on level <n>, higher levels have a faster ball
+    --seed <n>            seed for the ball's launch angles
+    --ruleset <name>      'classic' or 'practice' (balls never run out)
+    --mute                start with sound muted
+    --no-audio            don't open an audio device at all
+    --sound-pack <dir>    replace the built in sounds with the ones in <dir>
+    --windowed            run in a window
+    --fullscreen          run fullscreen
+    --terminal            play in the terminal instead of a window
+    --reduced-motion      turn off screen shake and hit-stop
+    --sprites <path>      draw with the sprite atlas described in <path>
+    --theme <name>        color theme: 'classic', 'dark', 'high-contrast', 'deuteranopia',
+                          'protanopia', 'tritanopia', or one from the themes directory
+    --debug               start with the debug overlay showing
+    --frame-stats <path>  save how long each frame took to <path> on exit, as JSON if it ends
+                          in '.json', otherwise CSV
+    --no-batching         upload everything every frame, for comparing draw times
+    --vsync, --no-vsync   wait, or don't, for the display's refresh before showing a frame
+    --max-fps <n>         draw at most <n> frames a second, 0 for as many as possible
+    --tick-rate <n>       update the game <n> times a second, whatever the frame rate
+    --post <effects>      post-process frames with some of 'bloom', 'crt' and 'vignette', in
+                          the order given, e.g. 'bloom,crt', or 'off'
+    --screenshot <n>      save a screenshot of frame <n>
+    --screenshot-dir <dir>
+                          save screenshots in <dir> rather than the working directory
+    --record <path>       save a replay of the game to <path>
+    --export <path>       render the replay in <path> to video and sound files, then exit
+    --export-to <dir>     put exported files in <dir>, 'export' by default
+    --export-format <format>
+                          'png' (a numbered image per frame) or 'y4m' (one raw video file)
+    --help                print this message and exit
+
+keys:
+    escape, p             pause and resume
+    m                     mute and unmute
+    -, +                  turn the volume down and up
+    f12                   save a screenshot
+    t                     switch to the next color theme
+    f3                    show and hide collision boxes and frame stats
+
+in the terminal, as well:
+    left, right, a, d     move the paddle, or use the mouse
+    space, enter          start
+    q, ctrl+c             quit
+";
+
+/// Every tunable, loaded from the config file and then overridden from the command line
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    pub logic: LogicSettings,
+    pub video: VideoSettings,
+    pub audio: AudioSettings,
+    pub control: ControlSettings,
+    pub export: ExportSettings,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogicSettings {
+    pub level: u32,
+    pub seed: Option<u64>,
+    pub ruleset: Ruleset,
+    pub balls: u32,
+
+    pub paddle_width: f32,
+    pub paddle_height: f32,
+    pub paddle_color: [f32; 3],
+
+    pub ball_width: f32,
+    pub ball_height: f32,
+    pub ball_speed: f32,
+    pub ball_color: [f32; 3],
+
+    pub brick_width: f32,
+    pub brick_height: f32,
+    pub brick_rows: usize,
+    pub brick_columns: usize,
+    pub brick_color: [f32; 3],
+
+    pub playfield_color: [f32; 3],
+
+    /// Seconds the game freezes for after breaking a brick
+    pub hit_stop: f32,
+    /// Logic updates per second, however many frames are drawn
+    pub tick_rate: u32,
+}
+impl Default for LogicSettings {
+    fn default() -> LogicSettings {
+        LogicSettings {
+            level: 1,
+            seed: None,
+            ruleset: Ruleset::Classic,
+            balls: game_objs::BALLS,
+
+            paddle_width: game_objs::PADDLE_WIDTH,
+            paddle_height: game_objs::PADDLE_HEIGHT,
+            paddle_color: game_objs::PADDLE_COLOR,
+
+            ball_width: game_objs::BALL_WIDTH,
+            ball_height: game_objs::BALL_HEIGHT,
+            ball_speed: game_objs::BALL_SPEED,
+            ball_color: game_objs::BALL_COLOR,
+
+            brick_width: game_objs::BRICK_WIDTH,
+            brick_height: game_objs::BRICK_HEIGHT,
+            brick_rows: game_objs::BRICK_ROWS,
+            brick_columns: game_objs::BRICK_COLUMNS,
+            brick_color: game_objs::BRICK_COLOR,
+
+            playfield_color: game_objs::PLAYFIELD_COLOR,
+
+            hit_stop: crate::logic::HIT_STOP,
+            tick_rate: crate::pacing::TICK_RATE,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct VideoSettings {
+    pub backend: VideoBackend,
+    pub window_mode: WindowMode,
+    pub window_width: u32,
+    pub window_height: u32,
+    /// Brick debris and sparks
+    pub particles: bool,
+    pub max_particles: usize,
+    /// Fading trail behind the ball, `trail_length` frames long
+    pub ball_trail: bool,
+    pub trail_length: usize,
+    /// How hard the screen shakes, from 0 (not at all) to 1
+    pub screen_shake: f32,
+    /// No screen shake or hit-stop, whatever they're set to
+    pub reduced_motion: bool,
+    /// Description of a sprite atlas to draw with, see [`video::atlas::SpriteSheet`]
+    pub sprite_atlas: Option<PathBuf>,
+    /// Name of the color theme to start with, see [`video::theme::Theme`]
+    pub theme: String,
+    /// Where to look for more themes, `themes` next to the config file if not given
+    pub theme_dir: Option<PathBuf>,
+    /// Keep unchanging geometry on the GPU and instance repeated quads, rather than uploading
+    /// everything every frame
+    pub persistent_buffers: bool,
+    /// Wait for the display's refresh before showing each frame
+    pub vsync: bool,
+    /// Most frames drawn a second, 0 for no limit
+    pub max_fps: u32,
+    /// Start with collision boxes and frame stats drawn over the game
+    pub debug_overlay: bool,
+    /// Keep every frame's timings and save them here on the way out, see
+    /// [`crate::stats::FrameStats::save`]
+    pub frame_stats: Option<PathBuf>,
+    /// Where screenshots are saved, the working directory if not given
+    pub screenshot_dir: Option<PathBuf>,
+    /// Take a screenshot of this frame, counting from 0
+    pub screenshot_at: Option<u64>,
+    pub post: PostSettings,
+}
+impl Default for VideoSettings {
+    fn default() -> VideoSettings {
+        VideoSettings {
+            backend: VideoBackend::Window,
+            window_mode: WindowMode::Windowed,
+            window_width: 800,
+            window_height: 600,
+            particles: true,
+            max_particles: video::particles::MAX_PARTICLES,
+            ball_trail: true,
+            trail_length: video::trail::TRAIL_LENGTH,
+            screen_shake: 1.0,
+            reduced_motion: false,
+            sprite_atlas: None,
+            theme: "classic".to_owned(),
+            theme_dir: None,
+            persistent_buffers: true,
+            vsync: true,
+            max_fps: 0,
+            debug_overlay: false,
+            frame_stats: None,
+            screenshot_dir: None,
+            screenshot_at: None,
+            post: PostSettings::default(),
+        }
+    }
+}
+
+/// Effects applied to each whole frame once it's drawn, see [`video::post`]
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PostSettings {
+    /// If false, frames go straight to the screen
+    pub enabled: bool,
+    /// Applied in this order
+    pub chain: Vec<PostEffect>,
+    /// How dark every other row gets, from 0 to 1
+    pub scanlines: f32,
+    /// How much the screen bulges, 0 is flat
+    pub curvature: f32,
+    /// How bright something has to be to glow, from 0 to 1
+    pub bloom_threshold: f32,
+    pub bloom_strength: f32,
+    /// How dark the corners get, from 0 to 1
+    pub vignette: f32,
+}
+impl Default for PostSettings {
+    fn default() -> PostSettings {
+        PostSettings {
+            enabled: false,
+            chain: vec![PostEffect::Bloom, PostEffect::Crt, PostEffect::Vignette],
+            scanlines: video::post::SCANLINES,
+            curvature: video::post::CURVATURE,
+            bloom_threshold: video::post::BLOOM_THRESHOLD,
+            bloom_strength: video::post::BLOOM_STRENGTH,
+            vignette: video::post::VIGNETTE,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PostEffect {
+    /// Bright things glow
+    Bloom,
+    /// Scanlines and a curved screen
+    Crt,
+    /// Darkened corners
+    Vignette,
+}
+impl FromStr for PostEffect {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<PostEffect, String> {
+        match s {
+            "bloom" => Ok(PostEffect::Bloom),
+            "crt" => Ok(PostEffect::Crt),
+            "vignette" => Ok(PostEffect::Vignette),
+            _ => Err(format!("unknown post-processing effect '{}'", s)),
+        }
+    }
+}
+
+/// e.g. `bloom,crt`, or `off` for none at all
+fn parse_post_chain(s: &str) -> Result<Vec<PostEffect>, String> {
+    if s == "off" {
+        return Ok(Vec::new());
+    }
+    s.split(',').map(|effect| effect.trim().parse()).collect()
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AudioSettings {
+    /// If false, don't even try to open an audio device
+    pub enabled: bool,
+    pub mute: bool,
+    /// Scales everything, effects and music
+    pub master_volume: f32,
+    pub sample_rate: i32,
+    /// How many sounds can play at once
+    pub max_voices: usize,
+    /// Pan sounds toward where they happened on the playfield
+    pub panning: bool,
+    /// Brick hits get higher pitched the higher the brick
+    pub pitch_bricks_by_row: bool,
+    pub effects_volume: f32,
+    pub music_volume: f32,
+    pub music: MusicSettings,
+    /// Where sounds come from when the sound pack doesn't have them
+    pub sounds: SoundSource,
+    pub synth: SynthSettings,
+    /// Directory of sound files replacing the built in sounds, see [`audio::assets::load_sounds`]
+    pub sound_pack: Option<PathBuf>,
+}
+impl Default for AudioSettings {
+    fn default() -> AudioSettings {
+        AudioSettings {
+            enabled: true,
+            mute: false,
+            master_volume: audio::MASTER_VOLUME,
+            sample_rate: audio::SAMPLE_RATE,
+            max_voices: audio::MAX_VOICES,
+            panning: true,
+            pitch_bricks_by_row: true,
+            effects_volume: audio::EFFECTS_VOLUME,
+            music_volume: audio::MUSIC_VOLUME,
+            music: MusicSettings::default(),
+            sounds: SoundSource::Samples,
+            synth: SynthSettings::default(),
+            sound_pack: None,
+        }
+    }
+}
+
+/// Tracks to loop, as WAV or Ogg Vorbis files. Anything left out is silence.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MusicSettings {
+    /// Before the game starts
+    pub menu: Option<PathBuf>,
+    /// One per level, the last one carries on for any levels after
+    pub play: Vec<PathBuf>,
+    pub game_over: Option<PathBuf>,
+    /// Seconds to fade from one track to the next
+    pub crossfade: f32,
+}
+impl Default for MusicSettings {
+    fn default() -> MusicSettings {
+        MusicSettings {
+            menu: None,
+            play: Vec::new(),
+            game_over: None,
+            crossfade: audio::MUSIC_CROSSFADE,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SoundSource {
+    /// The recordings built into the binary
+    Samples,
+    /// Generated at startup from [`SynthSettings`]
+    Synth,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SynthSettings {
+    pub paddle: SynthParams,
+    pub wall: SynthParams,
+    pub brick: SynthParams,
+}
+impl Default for SynthSettings {
+    fn default() -> SynthSettings {
+        SynthSettings {
+            paddle: SynthParams::paddle(),
+            wall: SynthParams::wall(),
+            brick: SynthParams::brick(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ControlSettings {
+    pub pause_on_focus_loss: bool,
+}
+impl Default for ControlSettings {
+    fn default() -> ControlSettings {
+        ControlSettings {
+            pause_on_focus_loss: true,
+        }
+    }
+}
+
+/// How replays are turned into video
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExportSettings {
+    pub dir: PathBuf,
+    pub format: ExportFormat,
+    /// Frames per second of the video, whatever the game was played at
+    pub fps: u32,
+}
+impl Default for ExportSettings {
+    fn default() -> ExportSettings {
+        ExportSettings {
+            dir: PathBuf::from("export"),
+            format: ExportFormat::Png,
+            fps: crate::export::FPS,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// `frame-000001.png` and so on
+    Png,
+    /// A single uncompressed YUV4MPEG2 stream, which most video tools read
+    Y4m,
+}
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<ExportFormat, String> {
+        match s {
+            "png" => Ok(ExportFormat::Png),
+            "y4m" => Ok(ExportFormat::Y4m),
+            _ => Err(format!("unknown export format '{}'", s)),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Ruleset {
+    /// Lose all your balls and it's game over
+    Classic,
+    /// Balls never run out
+    Practice,
+}
+impl FromStr for Ruleset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Ruleset, String> {
+        match s {
+            "classic" => Ok(Ruleset::Classic),
+            "practice" => Ok(Ruleset::Practice),
+            _ => Err(format!("unknown ruleset '{}'", s)),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WindowMode {
+    Windowed,
+    Fullscreen,
+}
+
+/// What the game is drawn to
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VideoBackend {
+    /// An OpenGL window
+    Window,
+    /// Half blocks in the terminal, e.g. over ssh
+    Terminal,
+}
+
+/// Overrides given on the command line
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Args {
+    pub help: bool,
+    pub config: Option<PathBuf>,
+
+    pub level: Option<u32>,
+    pub seed: Option<u64>,
+    pub ruleset: Option<Ruleset>,
+    pub mute: bool,
+    pub no_audio: bool,
+    pub sound_pack: Option<PathBuf>,
+    pub window_mode: Option<WindowMode>,
+    pub backend: Option<VideoBackend>,
+    pub reduced_motion: bool,
+    pub sprite_atlas: Option<PathBuf>,
+    pub theme: Option<String>,
+    pub no_batching: bool,
+    pub debug_overlay: bool,
+    pub frame_stats: Option<PathBuf>,
+    pub vsync: Option<bool>,
+    pub max_fps: Option<u32>,
+    pub tick_rate: Option<u32>,
+    pub screenshot_at: Option<u64>,
+    pub screenshot_dir: Option<PathBuf>,
+    /// Empty to turn post-processing off
+    pub post: Option<Vec<PostEffect>>,
+    pub record: Option<PathBuf>,
+    pub export: Option<PathBuf>,
+    pub export_dir: Option<PathBuf>,
+    pub export_format: Option<ExportFormat>,
+}
+impl Args {
+    /// Parse command line arguments, not including the program name
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Args, SettingsError> {
+        let mut parsed = Args::default();
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--help" | "-h" => parsed.help = true,
+                "--config" => parsed.config = Some(PathBuf::from(option_value(&arg, &mut args)?)),
+                "--level" => parsed.level = Some(parse_option_value(&arg, &mut args)?),
+                "--seed" => parsed.seed = Some(parse_option_value(&arg, &mut args)?),
+                "--ruleset" => parsed.ruleset = Some(parse_option_value(&arg, &mut args)?),
+                "--mute" => parsed.mute = true,
+                "--no-audio" => parsed.no_audio = true,
+                "--sound-pack" => {
+                    parsed.sound_pack = Some(PathBuf::from(option_value(&arg, &mut args)?))
+                }
+                "--windowed" => parsed.window_mode = Some(WindowMode::Windowed),
+                "--fullscreen" => parsed.window_mode = Some(WindowMode::Fullscreen),
+                "--terminal" => parsed.backend = Some(VideoBackend::Terminal),
+                "--reduced-motion" => parsed.reduced_motion = true,
+                "--no-batching" => parsed.no_batching = true,
+                "--debug" => parsed.debug_overlay = true,
+                "--frame-stats" => {
+                    parsed.frame_stats = Some(PathBuf::from(option_value(&arg, &mut args)?))
+                }
+                "--vsync" => parsed.vsync = Some(true),
+                "--no-vsync" => parsed.vsync = Some(false),
+                "--max-fps" => parsed.max_fps = Some(parse_option_value(&arg, &mut args)?),
+                "--tick-rate" => parsed.tick_rate = Some(parse_option_value(&arg, &mut args)?),
+                "--screenshot" => parsed.screenshot_at = Some(parse_option_value(&arg, &mut args)?),
+                "--screenshot-dir" => {
+                    parsed.screenshot_dir = Some(PathBuf::from(option_value(&arg, &mut args)?))
+                }
+                "--sprites" => {
+                    parsed.sprite_atlas = Some(PathBuf::from(option_value(&arg, &mut args)?))
+                }
+                "--post" => {
+                    let value = option_value(&arg, &mut args)?;
+                    parsed.post = Some(parse_post_chain(&value).map_err(|e| {
+                        SettingsError::Args(format!("bad value '{}' for '{}': {}", value, arg, e))
+                    })?)
+                }
+                "--theme" => parsed.theme = Some(option_value(&arg, &mut args)?),
+                "--record" => parsed.record = Some(PathBuf::from(option_value(&arg, &mut args)?)),
+                "--export" => parsed.export = Some(PathBuf::from(option_value(&arg, &mut args)?)),
+                "--export-to" => {
+                    parsed.export_dir = Some(PathBuf::from(option_value(&arg, &mut args)?))
+                }
+                "--export-format" => {
+                    parsed.export_format = Some(parse_option_value(&arg, &mut args)?)
+                }
+                _ => return Err(SettingsError::Args(format!("unknown option '{}'", arg))),
+            }
+        }
+
+        Ok(parsed)
+    }
+
+    /// Override `settings` with whatever was given on the command line
+    pub fn apply(&self, settings: &mut Settings) {
+        if let Some(level) = self.level {
+            settings.logic.level = level;
+        }
+        if self.seed.is_some() {
+            settings.logic.seed = self.seed;
+        }
+        if let Some(ruleset) = self.ruleset {
+            settings.logic.ruleset = ruleset;
+        }
+        if self.mute {
+            settings.audio.mute = true;
+        }
+        if self.no_audio {
+            settings.audio.enabled = false;
+        }
+        if self.sound_pack.is_some() {
+            settings.audio.sound_pack = self.sound_pack.clone();
+        }
+        if let Some(window_mode) = self.window_mode {
+            settings.video.window_mode = window_mode;
+        }
+        if let Some(backend) = self.backend {
+            settings.video.backend = backend;
+        }
+        if self.reduced_motion {
+            settings.video.reduced_motion = true;
+        }
+        if self.sprite_atlas.is_some() {
+            settings.video.sprite_atlas = self.sprite_atlas.clone();
+        }
+        if let Some(theme) = &self.theme {
+            settings.video.theme = theme.clone();
+        }
+        if self.no_batching {
+            settings.video.persistent_buffers = false;
+        }
+        if self.debug_overlay {
+            settings.video.debug_overlay = true;
+        }
+        if self.frame_stats.is_some() {
+            settings.video.frame_stats = self.frame_stats.clone();
+        }
+        if let Some(vsync) = self.vsync {
+            settings.video.vsync = vsync;
+        }
+        if let Some(max_fps) = self.max_fps {
+            settings.video.max_fps = max_fps;
+        }
+        if let Some(tick_rate) = self.tick_rate {
+            settings.logic.tick_rate = tick_rate;
+        }
+        if self.screenshot_at.is_some() {
+            settings.video.screenshot_at = self.screenshot_at;
+        }
+        if self.screenshot_dir.is_some() {
+            settings.video.screenshot_dir = self.screenshot_dir.clone();
+        }
+        if let Some(chain) = &self.post {
+            settings.video.post.enabled = !chain.is_empty();
+            if !chain.is_empty() {
+                settings.video.post.chain = chain.clone();
+            }
+        }
+        if let Some(dir) = &self.export_dir {
+            settings.export.dir = dir.clone();
+        }
+        if let Some(format) = self.export_format {
+            settings.export.format = format;
+        }
+    }
+}
+
+fn option_value(
+    option: &str,
+    args: &mut impl Iterator<Item = String>,
+) -> Result<String, SettingsError> {
+    args.next()
+        .ok_or_else(|| SettingsError::Args(format!("'{}' needs a value", option)))
+}
+
+fn parse_option_value<T>(
+    option: &str,
+    args: &mut impl Iterator<Item = String>,
+) -> Result<T, SettingsError>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    let value = option_value(option, args)?;
+    value
+        .parse()
+        .map_err(|e| SettingsError::Args(format!("bad value '{}' for '{}': {}", value, option, e)))
+}
+
+#[derive(Debug)]
+pub enum SettingsError {
+    Io(PathBuf, io::Error),
+    Write(PathBuf, io::Error),
+    Parse(PathBuf, toml::de::Error),
+    Serialize(toml::ser::Error),
+    Args(String),
+    Invalid(&'static str, String),
+}
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SettingsError::Io(path, e) => write!(f, "unable to read {}: {}", path.display(), e),
+            SettingsError::Write(path, e) => write!(f, "unable to write {}: {}", path.display(), e),
+            SettingsError::Parse(path, e) => write!(f, "unable to parse {}: {}", path.display(), e),
+            SettingsError::Serialize(e) => write!(f, "unable to serialize settings: {}", e),
+            SettingsError::Args(msg) => write!(f, "{}", msg),
+            SettingsError::Invalid(field, msg) => write!(f, "invalid setting '{}': {}", field, msg),
+        }
+    }
+}
+impl Error for SettingsError {}
+
+impl Settings {
+    /// Load the config file over the volume saved last time, apply command line overrides, and
+    /// check the result makes sense
+    ///
+    /// A missing config file is fine when it's the default one, we just use the defaults.
+    pub fn load(args: &Args) -> Result<Settings, SettingsError> {
+        let (config, path) = match &args.config {
+            Some(path) => (read_config(path)?, path.clone()),
+            None => match config_path() {
+                Some(path) if path.exists() => (read_config(&path)?, path),
+                _ => (toml::Table::new(), PathBuf::from("config")),
+            },
+        };
+        let mut settings = Settings::from_config(config.clone(), &path)?;
+
+        if let Some(volume) = load_volume() {
+            volume.apply(&mut settings.audio, &config);
+        }
+
+        args.apply(&mut settings);
+        settings.apply_reduced_motion();
+        settings.validate()?;
+
+        Ok(settings)
+    }
+
+    /// Turn off the motion the game itself makes, so logic only ever has to look at
+    /// `LogicSettings`, and replays hold what was actually played
+    pub fn apply_reduced_motion(&mut self) {
+        if self.video.reduced_motion {
+            self.logic.hit_stop = 0.0;
+        }
+    }
+
+    pub fn from_file(path: &Path) -> Result<Settings, SettingsError> {
+        Settings::from_config(read_config(path)?, path)
+    }
+
+    /// `path` is only for errors
+    fn from_config(config: toml::Table, path: &Path) -> Result<Settings, SettingsError> {
+        config
+            .try_into()
+            .map_err(|e| SettingsError::Parse(path.to_owned(), e))
+    }
+
+    pub fn validate(&self) -> Result<(), SettingsError> {
+        let logic = &self.logic;
+
+        if logic.level == 0 {
+            return Err(invalid("logic.level", "levels start at 1"));
+        }
+        for (field, value) in [
+            ("logic.paddle_width", logic.paddle_width),
+            ("logic.paddle_height", logic.paddle_height),
+            ("logic.ball_width", logic.ball_width),
+            ("logic.ball_height", logic.ball_height),
+            ("logic.brick_width", logic.brick_width),
+            ("logic.brick_height", logic.brick_height),
+        ] {
+            if !(value > 0.0 && value <= 2.0) {
+                return Err(invalid(
+                    field,
+                    "must be greater than 0 and at most 2 (the playfield's size)",
+                ));
+            }
+        }
+        if !(logic.ball_speed > 0.0 && logic.ball_speed.is_finite()) {
+            return Err(invalid("logic.ball_speed", "must be greater than 0"));
+        }
+        if logic.brick_rows == 0 {
+            return Err(invalid(
+                "logic.brick_rows",
+                "the brick grid needs at least one row",
+            ));
+        }
+        if logic.brick_columns == 0 {
+            return Err(invalid(
+                "logic.brick_columns",
+                "the brick grid needs at least one column",
+            ));
+        }
+        if logic.brick_columns as f32 * logic.brick_width > 2.0 + f32::EPSILON {
+            return Err(invalid(
+                "logic.brick_columns",
+                "the bricks don't fit side by side on the playfield",
+            ));
+        }
+        if logic.brick_rows as f32 * logic.brick_height > 1.0 + f32::EPSILON {
+            return Err(invalid(
+                "logic.brick_rows",
+                "the bricks don't fit in the top half of the playfield",
+            ));
+        }
+        for (field, color) in [
+            ("logic.paddle_color", logic.paddle_color),
+            ("logic.ball_color", logic.ball_color),
+            ("logic.brick_color", logic.brick_color),
+            ("logic.playfield_color", logic.playfield_color),
+        ] {
+            if color.iter().any(|c| !(0.0..=1.0).contains(c)) {
+                return Err(invalid(field, "color components go from 0 to 1"));
+            }
+        }
+
+        if self.video.max_particles > 65_536 {
+            return Err(invalid("video.max_particles", "must be at most 65536"));
+        }
+        if !(0.0..=0.5).contains(&self.logic.hit_stop) {
+            return Err(invalid(
+                "logic.hit_stop",
+                "must be between 0 and 0.5 seconds",
+            ));
+        }
+        if !(10..=1000).contains(&self.logic.tick_rate) {
+            return Err(invalid("logic.tick_rate", "must be between 10 and 1000"));
+        }
+        if self.video.max_fps > 1000 {
+            return Err(invalid(
+                "video.max_fps",
+                "must be at most 1000, or 0 for no limit",
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.video.screen_shake) {
+            return Err(invalid("video.screen_shake", "must be between 0 and 1"));
+        }
+        if !(2..=64).contains(&self.video.trail_length) {
+            return Err(invalid("video.trail_length", "must be between 2 and 64"));
+        }
+        let post = &self.video.post;
+        for (field, value, max) in [
+            ("video.post.scanlines", post.scanlines, 1.0),
+            ("video.post.curvature", post.curvature, 0.5),
+            ("video.post.bloom_threshold", post.bloom_threshold, 1.0),
+            ("video.post.bloom_strength", post.bloom_strength, 2.0),
+            ("video.post.vignette", post.vignette, 1.0),
+        ] {
+            if !(0.0..=max).contains(&value) {
+                return Err(SettingsError::Invalid(
+                    field,
+                    format!("must be between 0 and {}", max),
+                ));
+            }
+        }
+        if self.video.window_width == 0 || self.video.window_height == 0 {
+            return Err(invalid(
+                "video.window_width",
+                "the window needs a nonzero size",
+            ));
+        }
+
+        if !(1..=240).contains(&self.export.fps) {
+            return Err(invalid("export.fps", "must be between 1 and 240"));
+        }
+
+        for (field, volume) in [
+            ("audio.master_volume", self.audio.master_volume),
+            ("audio.effects_volume", self.audio.effects_volume),
+            ("audio.music_volume", self.audio.music_volume),
+        ] {
+            if !(0.0..=1.0).contains(&volume) {
+                return Err(invalid(field, "must be between 0 and 1"));
+            }
+        }
+        if !(0.0..=30.0).contains(&self.audio.music.crossfade) {
+            return Err(invalid(
+                "audio.music.crossfade",
+                "must be between 0 and 30 seconds",
+            ));
+        }
+        if !(8_000..=192_000).contains(&self.audio.sample_rate) {
+            return Err(invalid(
+                "audio.sample_rate",
+                "must be between 8000 and 192000",
+            ));
+        }
+        if !(1..=64).contains(&self.audio.max_voices) {
+            return Err(invalid("audio.max_voices", "must be between 1 and 64"));
+        }
+        for (field, params) in [
+            ("audio.synth.paddle", &self.audio.synth.paddle),
+            ("audio.synth.wall", &self.audio.synth.wall),
+            ("audio.synth.brick", &self.audio.synth.brick),
+        ] {
+            validate_synth(field, params, self.audio.sample_rate)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn validate_synth(
+    field: &'static str,
+    params: &SynthParams,
+    sample_rate: i32,
+) -> Result<(), SettingsError> {
+    let nyquist = sample_rate as f32 / 2.0;
+    for frequency in [params.frequency, params.frequency_end] {
+        if !(frequency > 0.0 && frequency < nyquist) {
+            return Err(SettingsError::Invalid(
+                field,
+                format!("frequencies must be between 0 and {} Hz", nyquist),
+            ));
+        }
+    }
+    if !(params.duty > 0.0 && params.duty < 1.0) {
+        return Err(invalid(field, "duty must be between 0 and 1"));
+    }
+    if !(0.0..=1.0).contains(&params.volume) {
+        return Err(invalid(field, "volume must be between 0 and 1"));
+    }
+    let envelope = &params.envelope;
+    if [
+        envelope.attack,
+        envelope.decay,
+        envelope.hold,
+        envelope.release,
+    ]
+    .iter()
+    .any(|time| time.is_nan() || *time < 0.0)
+        || !(0.0..=1.0).contains(&envelope.sustain)
+    {
+        return Err(invalid(
+            field,
+            "envelope times can't be negative, and sustain goes from 0 to 1",
+        ));
+    }
+    if !(envelope.duration() > 0.0 && envelope.duration() <= 5.0) {
+        return Err(invalid(field, "sounds must last between 0 and 5 seconds"));
+    }
+    Ok(())
+}
+
+fn invalid(field: &'static str, msg: &str) -> SettingsError {
+    SettingsError::Invalid(field, msg.to_owned())
+}
+
+/// `$XDG_CONFIG_HOME/breakout`, falling back on `~/.config/breakout`
+pub fn config_dir() -> Option<PathBuf> {
+    let base = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+    };
+    Some(base.join("breakout"))
+}
+
+pub fn config_path() -> Option<PathBuf> {
+    Some(config_dir()?.join("config.toml"))
+}
+
+/// Where the volume keys' changes get remembered between runs, see [`SavedVolume`]
+pub fn volume_path() -> Option<PathBuf> {
+    Some(config_dir()?.join("volume.toml"))
+}
+
+fn read_config(path: &Path) -> Result<toml::Table, SettingsError> {
+    let text = fs::read_to_string(path).map_err(|e| SettingsError::Io(path.to_owned(), e))?;
+    toml::from_str(&text).map_err(|e| SettingsError::Parse(path.to_owned(), e))
+}
+
+/// Whatever was changed with the volume keys in earlier runs. Anything not changed is left
+/// out, so it doesn't stop the config file from setting it.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SavedVolume {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub master: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub effects: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub music: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub muted: Option<bool>,
+}
+impl SavedVolume {
+    pub fn is_valid(&self) -> bool {
+        [self.master, self.effects, self.music]
+            .iter()
+            .flatten()
+            .all(|volume| (0.0..=1.0).contains(volume))
+    }
+
+    /// Override `audio` with what was saved, except for anything set in `config`'s `[audio]`
+    pub fn apply(&self, audio: &mut AudioSettings, config: &toml::Table) {
+        let configured = |key: &str| {
+            config
+                .get("audio")
+                .and_then(|audio| audio.as_table())
+                .is_some_and(|audio| audio.contains_key(key))
+        };
+        for (key, saved, setting) in [
+            ("master_volume", self.master, &mut audio.master_volume),
+            ("effects_volume", self.effects, &mut audio.effects_volume),
+            ("music_volume", self.music, &mut audio.music_volume),
+        ] {
+            if let Some(saved) = saved
+                && !configured(key)
+            {
+                *setting = saved;
+            }
+        }
+        if let Some(muted) = self.muted
+            && !configured("mute")
+        {
+            audio.mute = muted;
+        }
+    }
+
+    /// Add whatever's different between `before` and `after`
+    pub fn with_changes(self, before: &Volume, after: &Volume) -> SavedVolume {
+        let changed = |before: f32, after: f32| (before != after).then_some(after);
+        SavedVolume {
+            master: changed(before.master, after.master).or(self.master),
+            effects: changed(before.effects, after.effects).or(self.effects),
+            music: changed(before.music, after.music).or(self.music),
+            muted: (before.muted != after.muted)
+                .then_some(after.muted)
+                .or(self.muted),
+        }
+    }
+}
+
+/// The volume saved last time, if there is one and it makes sense
+fn load_volume() -> Option<SavedVolume> {
+    let path = volume_path()?;
+    let text = fs::read_to_string(&path).ok()?;
+    match toml::from_str::<SavedVolume>(&text) {
+        Ok(volume) if volume.is_valid() => Some(volume),
+        _ => {
+            eprintln!("warning: ignoring bad saved volume in {}", path.display());
+            None
+        }
+    }
+}
+
+/// Remember whatever the volume keys changed this run, going from `before` to `after`, on top
+/// of what was saved before. Command line overrides are left alone, since they're in both.
+pub fn save_volume(before: &Volume, after: &Volume) -> Result<(), SettingsError> {
+    if before == after {
+        return Ok(());
+    }
+    let Some(path) = volume_path() else {
+        return Ok(());
+    };
+    let volume = load_volume()
+        .unwrap_or_default()
+        .with_changes(before, after);
+    let text = toml::to_string(&volume).map_err(SettingsError::Serialize)?;
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| SettingsError::Write(dir.to_owned(), e))?;
+    }
+    fs::write(&path, text).map_err(|e| SettingsError::Write(path, e))
+}
@@ -0,0 +1,233 @@
+use crate::settings::{
+    Args, PostEffect, Ruleset, SavedVolume, Settings, SettingsError, WindowMode,
+};
+use crate::view::audio::Volume;
+
+fn args(args: &[&str]) -> Result<Args, SettingsError> {
+    Args::parse(args.iter().map(|arg| arg.to_string()))
+}
+
+#[test]
+fn args_override_config() {
+    let mut settings: Settings = toml::from_str(
+        "
+        [logic]
+        level = 3
+        ruleset = 'practice'
+        brick_rows = 4
+        ",
+    )
+    .unwrap();
+
+    args(&[
+        "--level",
+        "5",
+        "--seed",
+        "42",
+        "--mute",
+        "--fullscreen",
+        "--debug",
+        "--frame-stats",
+        "stats.csv",
+    ])
+    .unwrap()
+    .apply(&mut settings);
+
+    assert_eq!(settings.logic.level, 5);
+    assert_eq!(settings.logic.seed, Some(42));
+    assert_eq!(settings.logic.ruleset, Ruleset::Practice);
+    assert_eq!(settings.logic.brick_rows, 4);
+    assert!(settings.audio.mute);
+    assert_eq!(settings.video.window_mode, WindowMode::Fullscreen);
+    assert!(settings.video.debug_overlay);
+    assert_eq!(
+        settings.video.frame_stats.as_deref(),
+        Some(std::path::Path::new("stats.csv"))
+    );
+    assert!(settings.validate().is_ok());
+}
+
+#[test]
+fn no_audio_turns_sound_off_without_muting() {
+    let mut settings = Settings::default();
+    assert!(settings.audio.enabled);
+
+    args(&["--no-audio"]).unwrap().apply(&mut settings);
+    assert!(!settings.audio.enabled);
+    assert!(!settings.audio.mute);
+}
+
+#[test]
+fn reduced_motion_turns_off_hit_stop() {
+    let mut settings = Settings::default();
+    assert!(settings.logic.hit_stop > 0.0);
+
+    args(&["--reduced-motion"]).unwrap().apply(&mut settings);
+    settings.apply_reduced_motion();
+    assert!(settings.video.reduced_motion);
+    assert_eq!(settings.logic.hit_stop, 0.0);
+}
+
+#[test]
+fn post_effects_are_chained_in_order() {
+    let mut settings = Settings::default();
+    assert!(!settings.video.post.enabled);
+
+    args(&["--post", "vignette,bloom"])
+        .unwrap()
+        .apply(&mut settings);
+    assert!(settings.video.post.enabled);
+    assert_eq!(
+        settings.video.post.chain,
+        [PostEffect::Vignette, PostEffect::Bloom]
+    );
+
+    args(&["--post", "off"]).unwrap().apply(&mut settings);
+    assert!(!settings.video.post.enabled);
+
+    assert!(matches!(
+        args(&["--post", "crt,blur"]),
+        Err(SettingsError::Args(_))
+    ));
+}
+
+#[test]
+fn pacing_args_override_config() {
+    let mut settings = Settings::default();
+    assert!(settings.video.vsync);
+
+    args(&["--no-vsync", "--max-fps", "144", "--tick-rate", "240"])
+        .unwrap()
+        .apply(&mut settings);
+    assert!(!settings.video.vsync);
+    assert_eq!(settings.video.max_fps, 144);
+    assert_eq!(settings.logic.tick_rate, 240);
+    assert!(settings.validate().is_ok());
+
+    settings.logic.tick_rate = 0;
+    assert!(matches!(
+        settings.validate(),
+        Err(SettingsError::Invalid("logic.tick_rate", _))
+    ));
+}
+
+#[test]
+fn bad_args_are_rejected() {
+    assert!(matches!(args(&["--level"]), Err(SettingsError::Args(_))));
+    assert!(matches!(
+        args(&["--level", "two"]),
+        Err(SettingsError::Args(_))
+    ));
+    assert!(matches!(
+        args(&["--ruleset", "easy"]),
+        Err(SettingsError::Args(_))
+    ));
+    assert!(matches!(args(&["--turbo"]), Err(SettingsError::Args(_))));
+}
+
+#[test]
+fn config_errors_name_the_file() {
+    let dir = std::env::temp_dir().join("breakout-config-error-test");
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("config.toml");
+    std::fs::write(&path, "[logic]\nbrick_rows = 'lots'\n").unwrap();
+
+    let config = path.to_str().unwrap();
+    match Settings::load(&args(&["--config", config]).unwrap()) {
+        Err(e @ SettingsError::Parse(..)) => assert!(e.to_string().contains(config)),
+        other => panic!("expected a parse error, got {:?}", other.map(|_| ())),
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn bad_values_are_rejected() {
+    assert!(Settings::default().validate().is_ok());
+
+    let mut settings = Settings::default();
+    settings.logic.level = 0;
+    assert!(matches!(
+        settings.validate(),
+        Err(SettingsError::Invalid("logic.level", _))
+    ));
+
+    let mut settings = Settings::default();
+    settings.logic.brick_columns = 0;
+    assert!(matches!(
+        settings.validate(),
+        Err(SettingsError::Invalid("logic.brick_columns", _))
+    ));
+
+    let mut settings = Settings::default();
+    settings.logic.brick_rows = 0;
+    assert!(matches!(
+        settings.validate(),
+        Err(SettingsError::Invalid("logic.brick_rows", _))
+    ));
+
+    let mut settings = Settings::default();
+    settings.logic.brick_columns = 40;
+    assert!(matches!(
+        settings.validate(),
+        Err(SettingsError::Invalid("logic.brick_columns", _))
+    ));
+
+    let mut settings = Settings::default();
+    settings.audio.master_volume = 2.0;
+    assert!(matches!(
+        settings.validate(),
+        Err(SettingsError::Invalid("audio.master_volume", _))
+    ));
+
+    assert!(toml::from_str::<Settings>("[logic]\npaddle_wdith = 0.5").is_err());
+}
+
+#[test]
+fn only_changed_volume_is_saved() {
+    // started muted from the command line, then turned up
+    let before = Volume {
+        muted: true,
+        ..Volume::default()
+    };
+    let after = Volume {
+        master: 0.75,
+        ..before
+    };
+    let saved = SavedVolume {
+        music: Some(0.25),
+        ..SavedVolume::default()
+    }
+    .with_changes(&before, &after);
+    assert_eq!(
+        saved,
+        SavedVolume {
+            master: Some(0.75),
+            music: Some(0.25),
+            ..SavedVolume::default()
+        }
+    );
+    assert_eq!(
+        toml::to_string(&saved).unwrap(),
+        "master = 0.75\nmusic = 0.25\n"
+    );
+
+    // files from before only what changed was saved still load
+    let old: SavedVolume =
+        toml::from_str("master = 0.5\neffects = 1.0\nmusic = 0.5\nmuted = true").unwrap();
+    assert_eq!(old.muted, Some(true));
+    assert!(old.is_valid());
+}
+
+#[test]
+fn config_wins_over_saved_volume() {
+    let config: toml::Table = toml::from_str("[audio]\nmaster_volume = 0.3").unwrap();
+    let mut settings: Settings = config.clone().try_into().unwrap();
+    SavedVolume {
+        master: Some(0.9),
+        muted: Some(true),
+        ..SavedVolume::default()
+    }
+    .apply(&mut settings.audio, &config);
+    assert_eq!(settings.audio.master_volume, 0.3);
+    assert!(settings.audio.mute);
+}
@@ -0,0 +1,315 @@
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+#[cfg(test)]
+mod tests;
+
+/// How many of the latest frames are always kept, enough for the debug overlay's graph
+pub const RECENT_FRAMES: usize = 120;
+/// Without every frame kept, session percentiles are rounded down to this
+pub const HISTOGRAM_BUCKET: Duration = Duration::from_micros(10);
+/// Anything slower counts as this, in the session percentiles without every frame kept
+pub const HISTOGRAM_MAX: Duration = Duration::from_millis(100);
+
+/// How long each part of one frame took
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct FrameTiming {
+    /// All the logic ticks run for the frame
+    pub logic: Duration,
+    /// Moving effects along and drawing, up to handing the frame over
+    pub render: Duration,
+    /// Showing the frame, which is where waiting on vsync happens
+    pub present: Duration,
+}
+impl FrameTiming {
+    pub fn total(&self) -> Duration {
+        self.logic + self.render + self.present
+    }
+}
+
+/// How long frames took at the 50th, 95th and 99th percentiles
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Percentiles {
+    pub p50: Duration,
+    pub p95: Duration,
+    pub p99: Duration,
+}
+impl Percentiles {
+    /// Nearest rank, so every percentile is a time some frame actually took
+    pub fn of(times: impl Iterator<Item = Duration>) -> Percentiles {
+        let mut times: Vec<Duration> = times.collect();
+        if times.is_empty() {
+            return Percentiles::default();
+        }
+        times.sort_unstable();
+
+        let rank = |percentile: usize| times[nearest_rank(percentile, times.len()) - 1];
+        Percentiles {
+            p50: rank(50),
+            p95: rank(95),
+            p99: rank(99),
+        }
+    }
+}
+
+/// Which of `count` sorted times, counting from 1, is at `percentile`
+fn nearest_rank(percentile: usize, count: usize) -> usize {
+    (percentile * count).div_ceil(100).clamp(1, count)
+}
+impl fmt::Display for Percentiles {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "p50 {:.2} p95 {:.2} p99 {:.2} ms",
+            millis(self.p50),
+            millis(self.p95),
+            millis(self.p99)
+        )
+    }
+}
+
+/// Percentiles for each part of a frame, and the whole thing
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Summary {
+    pub frames: usize,
+    pub logic: Percentiles,
+    pub render: Percentiles,
+    pub present: Percentiles,
+    pub total: Percentiles,
+}
+impl Summary {
+    pub fn of<'a>(frames: impl Iterator<Item = &'a FrameTiming> + Clone) -> Summary {
+        Summary {
+            frames: frames.clone().count(),
+            logic: Percentiles::of(frames.clone().map(|frame| frame.logic)),
+            render: Percentiles::of(frames.clone().map(|frame| frame.render)),
+            present: Percentiles::of(frames.clone().map(|frame| frame.present)),
+            total: Percentiles::of(frames.map(FrameTiming::total)),
+        }
+    }
+
+    /// Each part's name with its percentiles
+    pub fn phases(&self) -> [(&'static str, Percentiles); 4] {
+        [
+            ("logic", self.logic),
+            ("render", self.render),
+            ("present", self.present),
+            ("total", self.total),
+        ]
+    }
+}
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, percentiles) in self.phases() {
+            writeln!(f, "{} time: {}", name, percentiles)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum StatsError {
+    Write(PathBuf, io::Error),
+}
+impl fmt::Display for StatsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StatsError::Write(path, e) => write!(f, "unable to write {}: {}", path.display(), e),
+        }
+    }
+}
+impl Error for StatsError {}
+
+/// Counts of times in `HISTOGRAM_BUCKET` wide buckets, so percentiles over a whole session
+/// take the same memory however long it goes on
+#[derive(Clone, Debug)]
+struct Histogram {
+    /// The last bucket is everything from `HISTOGRAM_MAX` up
+    counts: Vec<u32>,
+    total: usize,
+}
+impl Histogram {
+    fn new() -> Histogram {
+        let buckets = (HISTOGRAM_MAX.as_nanos() / HISTOGRAM_BUCKET.as_nanos()) as usize;
+        Histogram {
+            counts: vec![0; buckets + 1],
+            total: 0,
+        }
+    }
+
+    fn record(&mut self, time: Duration) {
+        let bucket = (time.as_nanos() / HISTOGRAM_BUCKET.as_nanos()) as usize;
+        let last = self.counts.len() - 1;
+        self.counts[bucket.min(last)] += 1;
+        self.total += 1;
+    }
+
+    /// Nearest rank like [`Percentiles::of`], but only to the start of the bucket
+    fn percentiles(&self) -> Percentiles {
+        if self.total == 0 {
+            return Percentiles::default();
+        }
+
+        let rank = |percentile: usize| {
+            let rank = nearest_rank(percentile, self.total);
+            let mut seen = 0;
+            let bucket = self
+                .counts
+                .iter()
+                .position(|&count| {
+                    seen += count as usize;
+                    seen >= rank
+                })
+                .unwrap_or_default();
+            HISTOGRAM_BUCKET * bucket as u32
+        };
+        Percentiles {
+            p50: rank(50),
+            p95: rank(95),
+            p99: rank(99),
+        }
+    }
+}
+
+/// Timings for the session, for catching performance regressions
+///
+/// Only the latest frames are kept one by one unless asked for, everything else is just counted
+/// up, so a long session doesn't keep growing.
+#[derive(Clone, Debug)]
+pub struct FrameStats {
+    /// Newest last
+    recent: VecDeque<FrameTiming>,
+    /// Every frame, when they're going to be saved
+    history: Option<Vec<FrameTiming>>,
+    logic: Histogram,
+    render: Histogram,
+    present: Histogram,
+    total: Histogram,
+    render_time: Duration,
+}
+impl FrameStats {
+    /// Keeping every frame if `keep_history`, e.g. to save them
+    pub fn new(keep_history: bool) -> FrameStats {
+        FrameStats {
+            recent: VecDeque::with_capacity(RECENT_FRAMES),
+            history: keep_history.then(Vec::new),
+            logic: Histogram::new(),
+            render: Histogram::new(),
+            present: Histogram::new(),
+            total: Histogram::new(),
+            render_time: Duration::ZERO,
+        }
+    }
+
+    pub fn record(&mut self, timing: FrameTiming) {
+        if self.recent.len() == RECENT_FRAMES {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(timing);
+        if let Some(history) = &mut self.history {
+            history.push(timing);
+        }
+
+        self.logic.record(timing.logic);
+        self.render.record(timing.render);
+        self.present.record(timing.present);
+        self.total.record(timing.total());
+        self.render_time += timing.render;
+    }
+
+    /// Every frame so far, or none if they aren't being kept
+    pub fn frames(&self) -> &[FrameTiming] {
+        self.history.as_deref().unwrap_or_default()
+    }
+
+    /// Average time spent on `render`
+    pub fn average_render(&self) -> Duration {
+        self.render_time / self.total.total.max(1) as u32
+    }
+
+    /// Over the whole session, exactly if every frame's kept
+    pub fn summary(&self) -> Summary {
+        match &self.history {
+            Some(history) => Summary::of(history.iter()),
+            None => Summary {
+                frames: self.total.total,
+                logic: self.logic.percentiles(),
+                render: self.render.percentiles(),
+                present: self.present.percentiles(),
+                total: self.total.percentiles(),
+            },
+        }
+    }
+
+    /// Over just the last `RECENT_FRAMES` frames
+    pub fn recent_summary(&self) -> Summary {
+        Summary::of(self.recent.iter())
+    }
+
+    /// As JSON if `path` ends in `.json`, otherwise CSV
+    pub fn save(&self, path: &Path) -> Result<(), StatsError> {
+        let text = match path.extension().and_then(|extension| extension.to_str()) {
+            Some("json") => self.to_json(),
+            _ => self.to_csv(),
+        };
+        fs::write(path, text).map_err(|e| StatsError::Write(path.to_owned(), e))
+    }
+
+    /// A row per frame, in milliseconds
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("frame,logic_ms,render_ms,present_ms,total_ms\n");
+        for (index, frame) in self.frames().iter().enumerate() {
+            csv.push_str(&format!(
+                "{},{:.4},{:.4},{:.4},{:.4}\n",
+                index,
+                millis(frame.logic),
+                millis(frame.render),
+                millis(frame.present),
+                millis(frame.total())
+            ));
+        }
+        csv
+    }
+
+    /// The summary, then every frame as `[logic, render, present]`, all in milliseconds
+    pub fn to_json(&self) -> String {
+        let summary = self.summary();
+        let mut json = format!("{{\n  \"frames\": {},\n  \"summary\": {{\n", summary.frames);
+        let phases = summary.phases();
+        for (index, (name, percentiles)) in phases.iter().enumerate() {
+            json.push_str(&format!(
+                "    \"{}\": {{ \"p50_ms\": {:.4}, \"p95_ms\": {:.4}, \"p99_ms\": {:.4} }}{}\n",
+                name,
+                millis(percentiles.p50),
+                millis(percentiles.p95),
+                millis(percentiles.p99),
+                if index + 1 < phases.len() { "," } else { "" }
+            ));
+        }
+        json.push_str("  },\n  \"frame_times_ms\": [");
+        for (index, frame) in self.frames().iter().enumerate() {
+            json.push_str(&format!(
+                "{}\n    [{:.4}, {:.4}, {:.4}]",
+                if index > 0 { "," } else { "" },
+                millis(frame.logic),
+                millis(frame.render),
+                millis(frame.present)
+            ));
+        }
+        json.push_str(if self.frames().is_empty() {
+            "]\n}\n"
+        } else {
+            "\n  ]\n}\n"
+        });
+        json
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
@@ -0,0 +1,88 @@
+use std::time::Duration;
+
+use crate::stats::{FrameStats, FrameTiming, Percentiles, HISTOGRAM_MAX, RECENT_FRAMES};
+
+fn millis(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn percentiles_are_nearest_rank() {
+    let percentiles = Percentiles::of((1..=100).map(millis));
+    assert_eq!(percentiles.p50, millis(50));
+    assert_eq!(percentiles.p95, millis(95));
+    assert_eq!(percentiles.p99, millis(99));
+
+    // order doesn't matter, and one slow frame shows up at the top
+    let percentiles = Percentiles::of([millis(40), millis(2), millis(1), millis(3)].into_iter());
+    assert_eq!(percentiles.p50, millis(2));
+    assert_eq!(percentiles.p99, millis(40));
+
+    assert_eq!(Percentiles::of(std::iter::empty()), Percentiles::default());
+}
+
+#[test]
+fn frames_are_summarised_and_saved() {
+    let mut stats = FrameStats::new(true);
+    for ms in 1..=10 {
+        stats.record(FrameTiming {
+            logic: millis(1),
+            render: millis(ms),
+            present: millis(2),
+        });
+    }
+
+    let summary = stats.summary();
+    assert_eq!(summary.frames, 10);
+    assert_eq!(summary.logic.p99, millis(1));
+    assert_eq!(summary.render.p50, millis(5));
+    assert_eq!(summary.total.p99, millis(13));
+    assert_eq!(stats.recent_summary().render.p50, millis(5));
+    assert_eq!(stats.average_render(), Duration::from_micros(5500));
+
+    let csv = stats.to_csv();
+    assert_eq!(csv.lines().count(), 11);
+    assert_eq!(csv.lines().nth(1), Some("0,1.0000,1.0000,2.0000,4.0000"));
+
+    let json = stats.to_json();
+    assert!(json.contains("\"frames\": 10,"));
+    assert!(json.contains("\"render\": { \"p50_ms\": 5.0000, \"p95_ms\": 10.0000"));
+    assert!(json.contains("[1.0000, 10.0000, 2.0000]\n  ]"));
+
+    let dir = std::env::temp_dir().join("breakout-stats-test");
+    std::fs::create_dir_all(&dir).unwrap();
+    stats.save(&dir.join("stats.json")).unwrap();
+    stats.save(&dir.join("stats.csv")).unwrap();
+    assert_eq!(
+        std::fs::read_to_string(dir.join("stats.json")).unwrap(),
+        json
+    );
+    assert_eq!(std::fs::read_to_string(dir.join("stats.csv")).unwrap(), csv);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn long_sessions_are_counted_up_not_kept() {
+    let mut stats = FrameStats::new(false);
+    for ms in 1..=1000 {
+        stats.record(FrameTiming {
+            logic: Duration::from_micros(ms * 10 + 3),
+            render: millis(ms),
+            present: Duration::ZERO,
+        });
+    }
+    assert!(stats.frames().is_empty());
+
+    // rounded down to the bucket, and slow frames are cut off
+    let summary = stats.summary();
+    assert_eq!(summary.frames, 1000);
+    assert_eq!(summary.logic.p50, Duration::from_micros(5000));
+    assert_eq!(summary.logic.p99, Duration::from_micros(9900));
+    assert_eq!(summary.render.p50, HISTOGRAM_MAX);
+    assert_eq!(summary.present, Percentiles::default());
+
+    let recent = stats.recent_summary();
+    assert_eq!(recent.frames, RECENT_FRAMES);
+    assert_eq!(recent.render.p50, millis(940));
+    assert_eq!(stats.average_render(), Duration::from_micros(500_500));
+}
@@ -1,11 +1,16 @@
-use std::time::Instant;
+use std::path::PathBuf;
+use std::time::Duration;
 use winit::event_loop::EventLoop;
 
+use crate::control::ControlState;
 use crate::logic::LogicState;
+use crate::settings::Settings;
+use crate::stats::FrameStats;
 
 pub mod audio;
 use audio::AudioState;
 
+use self::video::screenshot::ScreenshotError;
 use self::video::VideoState;
 
 pub mod video;
@@ -16,26 +21,51 @@ mod tests;
 /// Information relevant to the renderer
 pub struct ViewState {
     frame_count: u64,
-    init_time: Instant,
 
     video_state: VideoState,
     audio_state: AudioState,
 }
 impl ViewState {
-    pub fn new(event_loop: &EventLoop<()>) -> ViewState {
+    pub fn new(event_loop: &EventLoop<()>, settings: &Settings) -> ViewState {
         ViewState {
             frame_count: 0,
-            init_time: Instant::now(),
 
-            video_state: VideoState::new(event_loop),
-            audio_state: AudioState::new(),
+            video_state: VideoState::new(event_loop, settings),
+            audio_state: AudioState::new(settings),
         }
     }
 
-    /// Draw a frame
-    pub fn update(&mut self, logic_state: &LogicState) {
-        self.audio_state.update(logic_state);
-        self.video_state.update(logic_state);
+    /// Play in the terminal, without a window
+    pub fn terminal(settings: &Settings) -> std::io::Result<ViewState> {
+        // sound first, so its warnings are printed before the terminal's taken over
+        let audio_state = AudioState::new(settings);
+        Ok(ViewState {
+            frame_count: 0,
+
+            video_state: VideoState::terminal(settings)?,
+            audio_state,
+        })
+    }
+
+    /// Draw a frame, after the game took `logic_time` to update for it
+    pub fn update(
+        &mut self,
+        logic_state: &LogicState,
+        control_state: &ControlState,
+        logic_time: Duration,
+    ) {
+        self.audio_state.update(logic_state, control_state);
+        if control_state.theme_change_requested(self.frame_count) {
+            self.video_state.next_theme();
+        }
+        self.video_state
+            .show_debug_overlay(control_state.debug_overlay());
+        self.video_state.update(
+            logic_state,
+            logic_time,
+            self.frame_count,
+            control_state.screenshot_requested(self.frame_count),
+        );
 
         self.frame_count += 1;
     }
@@ -48,7 +78,20 @@ impl ViewState {
         self.video_state.window_size()
     }
 
-    pub fn calculate_fps(&self) -> f32 {
-        self.frame_count as f32 / Instant::now().duration_since(self.init_time).as_secs_f32()
+    pub fn average_draw_time(&self) -> Duration {
+        self.video_state.average_draw_time()
+    }
+
+    /// How the screenshots taken since the last call went, to print when it's safe to
+    pub fn take_screenshots(&mut self) -> Vec<Result<PathBuf, ScreenshotError>> {
+        self.video_state.take_screenshots()
+    }
+
+    pub fn frame_stats(&self) -> &FrameStats {
+        self.video_state.frame_stats()
+    }
+
+    pub fn persistent_buffers(&self) -> bool {
+        self.video_state.persistent_buffers()
     }
 }
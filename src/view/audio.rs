@@ -1,70 +1,280 @@
-use alto::{Alto, Context, Source, Stereo, StreamingSource};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 
-use crate::logic::{interaction::Bounce, LogicState};
+use crate::control::ControlState;
+use crate::logic::{
+    interaction::{Bounce, Impact},
+    LogicState, Phase,
+};
+use crate::settings::{AudioSettings, MusicSettings, Settings};
 
-const SAMPLE_RATE: i32 = 44_000;
+pub mod assets;
+pub mod capture;
+pub mod mixer;
+pub mod music;
+pub mod openal;
+pub mod synth;
+pub mod voices;
+use capture::CapturingBackend;
+use openal::OpenAlBackend;
+
+#[cfg(test)]
+mod tests;
+
+/// What we hand OpenAL, sounds get resampled to this when loaded
+pub const SAMPLE_RATE: i32 = 44_100;
+/// What the built in sounds were recorded at
+pub const BUILTIN_SAMPLE_RATE: i32 = 44_100;
+pub const MASTER_VOLUME: f32 = 0.1;
+pub const EFFECTS_VOLUME: f32 = 1.0;
+pub const MUSIC_VOLUME: f32 = 0.5;
+/// How far music gets turned down while paused
+pub const PAUSE_DUCKING: f32 = 0.3;
+/// How much the volume hotkeys change the master volume by
+pub const VOLUME_STEP: f32 = 0.05;
+/// How long one track takes to fade into the next, in seconds
+pub const MUSIC_CROSSFADE: f32 = 2.0;
+pub const MAX_VOICES: usize = 8;
+/// Brick hits go from this pitch on the bottom row...
+pub const BRICK_PITCH_LOW: f32 = 0.8;
+/// ...to this one on the top row
+pub const BRICK_PITCH_HIGH: f32 = 1.25;
 
 pub const BYTES_BOUNCE_PADDLE: &[u8] = include_bytes!("audio/bounce-paddle.raw");
 pub const BYTES_BOUNCE_PLAYFIELD_BORDER: &[u8] = include_bytes!("audio/bounce-playfieldborder.raw");
 pub const BYTES_BOUNCE_BRICK: &[u8] = include_bytes!("audio/bounce-brick.raw");
 
+/// Every sound the game can make
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Sound {
+    BouncePaddle,
+    BouncePlayfieldBorder,
+    BounceBrick,
+}
+impl Sound {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Sound::BouncePaddle => "bounce-paddle",
+            Sound::BouncePlayfieldBorder => "bounce-playfieldborder",
+            Sound::BounceBrick => "bounce-brick",
+        }
+    }
+
+    /// Which sounds win when there's not enough voices for all of them, higher is more important
+    pub fn priority(&self) -> u32 {
+        match self {
+            Sound::BouncePaddle => 2,
+            Sound::BounceBrick => 1,
+            Sound::BouncePlayfieldBorder => 0,
+        }
+    }
+
+    pub const ALL: [Sound; 3] = [
+        Sound::BouncePaddle,
+        Sound::BouncePlayfieldBorder,
+        Sound::BounceBrick,
+    ];
+}
+
+/// How loud everything is, each from 0 to 1
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Volume {
+    pub master: f32,
+    pub effects: f32,
+    pub music: f32,
+    pub muted: bool,
+}
+impl Default for Volume {
+    fn default() -> Volume {
+        Volume::from(&AudioSettings::default())
+    }
+}
+impl From<&AudioSettings> for Volume {
+    fn from(settings: &AudioSettings) -> Volume {
+        Volume {
+            master: settings.master_volume,
+            effects: settings.effects_volume,
+            music: settings.music_volume,
+            muted: settings.mute,
+        }
+    }
+}
+impl Volume {
+    /// What actually comes out of the speakers, the master volume, or nothing if muted
+    pub fn output(&self) -> f32 {
+        if self.muted {
+            0.0
+        } else {
+            self.master
+        }
+    }
+
+    /// Music turned down while paused, so it's clear the game's not going
+    pub fn ducked(self, paused: bool) -> Volume {
+        if paused {
+            Volume {
+                music: self.music * PAUSE_DUCKING,
+                ..self
+            }
+        } else {
+            self
+        }
+    }
+}
+
+/// A sound, and how to play it
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SoundEvent {
+    pub sound: Sound,
+    /// -1 is all the way left, 1 all the way right
+    pub pan: f32,
+    /// Playback speed, 1 is as recorded
+    pub pitch: f32,
+}
+impl SoundEvent {
+    pub fn centered(sound: Sound) -> SoundEvent {
+        SoundEvent {
+            sound,
+            pan: 0.0,
+            pitch: 1.0,
+        }
+    }
+}
+
+/// Something that can make noise
+pub trait AudioBackend {
+    fn play(&mut self, event: SoundEvent);
+    /// Fade over to looping `track`, or fade out to silence if `None`
+    fn play_music(&mut self, track: Option<&Path>);
+    fn set_volume(&mut self, volume: Volume);
+    /// Called once a frame, for streaming and fades
+    fn update(&mut self);
+}
+
+/// Makes no noise at all
+pub struct NullBackend;
+impl AudioBackend for NullBackend {
+    fn play(&mut self, _event: SoundEvent) {}
+    fn play_music(&mut self, _track: Option<&Path>) {}
+    fn set_volume(&mut self, _volume: Volume) {}
+    fn update(&mut self) {}
+}
+
+/// Whatever `open` gives back, or silence if audio's turned off or it fails
+fn open_backend<E: std::fmt::Display>(
+    settings: &Settings,
+    open: impl FnOnce() -> Result<Box<dyn AudioBackend>, E>,
+) -> Box<dyn AudioBackend> {
+    if !settings.audio.enabled {
+        return Box::new(NullBackend);
+    }
+    match open() {
+        Ok(backend) => backend,
+        Err(e) => {
+            eprintln!(
+                "warning: unable to set up openal ({}), continuing without sound",
+                e
+            );
+            Box::new(NullBackend)
+        }
+    }
+}
+
 pub struct AudioState {
-    al_context: Context,
-    al_source: StreamingSource,
+    backend: Box<dyn AudioBackend>,
+
+    panning: bool,
+    pitch_bricks_by_row: bool,
+
+    music: MusicSettings,
+    music_track: Option<PathBuf>,
+    volume: Volume,
 }
 impl AudioState {
-    pub fn new() -> AudioState {
-        // set up openal
-        let alto =
-            Alto::load_default().expect("unable to load default openal implementation, exiting");
-
-        let al_device = alto
-            .open(None)
-            .expect("unable to open openal output device, exiting");
-
-        let al_context = al_device
-            .new_context(None)
-            .expect("unable to create openal context, exiting");
-        al_context
-            .set_gain(0.1)
-            .expect("unable to set openal context gain, exiting");
-
-        let al_source = al_context
-            .new_streaming_source()
-            .expect("unable to create openal source, exiting");
+    pub fn new(settings: &Settings) -> AudioState {
+        let backend = open_backend(settings, || {
+            OpenAlBackend::new(settings).map(|backend| Box::new(backend) as Box<dyn AudioBackend>)
+        });
+        AudioState::with_backend(backend, settings)
+    }
 
+    pub fn with_backend(backend: Box<dyn AudioBackend>, settings: &Settings) -> AudioState {
         AudioState {
-            al_context,
-            al_source,
+            backend,
+
+            panning: settings.audio.panning,
+            pitch_bricks_by_row: settings.audio.pitch_bricks_by_row,
+
+            music: settings.audio.music.clone(),
+            music_track: None,
+            volume: Volume::from(&settings.audio),
         }
     }
 
-    pub fn update(&mut self, logic_state: &LogicState) {
-        if let Some(bounce) = logic_state.bounce() {
-            if self.al_source.buffers_queued() == 1 {
-                self.al_source.stop();
-                self.al_source
-                    .unqueue_buffer()
-                    .expect("unable to unqueue al buffer, exiting");
-            }
+    /// Record sounds instead of playing them, see [`CapturingBackend::captured`]
+    pub fn capturing(settings: &Settings) -> (AudioState, CapturingBackend) {
+        let capture = CapturingBackend::new();
+        (
+            AudioState::with_backend(Box::new(capture.clone()), settings),
+            capture,
+        )
+    }
+
+    pub fn update(&mut self, logic_state: &LogicState, control_state: &ControlState) {
+        let volume = control_state.volume().ducked(logic_state.paused());
+        if volume != self.volume {
+            self.backend.set_volume(volume);
+            self.volume = volume;
+        }
 
-            let buffer = self
-                .al_context
-                .new_buffer::<Stereo<u8>, &[u8]>(
-                    match bounce {
-                        Bounce::Brick => BYTES_BOUNCE_BRICK,
-                        Bounce::Paddle => BYTES_BOUNCE_PADDLE,
-                        Bounce::PlayfieldBorder => BYTES_BOUNCE_PLAYFIELD_BORDER,
-                    },
-                    SAMPLE_RATE,
-                )
-                .expect("unable to create openal buffer, exiting");
-
-            self.al_source
-                .queue_buffer(buffer)
-                .expect("unable to queue openal buffer, exiting");
-
-            self.al_source.play();
+        for impact in logic_state.impacts() {
+            self.on_impact(*impact);
         }
+
+        let track = self.track_for(logic_state.phase(), logic_state.level());
+        if track != self.music_track {
+            self.backend.play_music(track.as_deref());
+            self.music_track = track;
+        }
+
+        self.backend.update();
+    }
+
+    /// What should be playing. Levels past the end of the playlist get its last track.
+    fn track_for(&self, phase: Phase, level: u32) -> Option<PathBuf> {
+        match phase {
+            Phase::Menu => self.music.menu.clone(),
+            Phase::Playing => self
+                .music
+                .play
+                .get(level.saturating_sub(1) as usize)
+                .or(self.music.play.last())
+                .cloned(),
+            Phase::GameOver => self.music.game_over.clone(),
+        }
+    }
+
+    fn on_impact(&mut self, impact: Impact) {
+        let mut event = SoundEvent::centered(bounce_to_sound(impact.bounce));
+
+        if self.panning {
+            event.pan = impact.x.clamp(-1.0, 1.0);
+        }
+        if self.pitch_bricks_by_row && impact.bounce == Bounce::Brick {
+            // bricks only live in the top half
+            let height = impact.y.clamp(0.0, 1.0);
+            event.pitch = BRICK_PITCH_LOW + (BRICK_PITCH_HIGH - BRICK_PITCH_LOW) * height;
+        }
+
+        self.backend.play(event);
+    }
+}
+
+fn bounce_to_sound(bounce: Bounce) -> Sound {
+    match bounce {
+        Bounce::Brick => Sound::BounceBrick,
+        Bounce::Paddle => Sound::BouncePaddle,
+        Bounce::PlayfieldBorder => Sound::BouncePlayfieldBorder,
     }
 }
@@ -0,0 +1,222 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, Read, Seek};
+use std::path::{Path, PathBuf};
+
+use crate::settings::{AudioSettings, SoundSource};
+use crate::view::audio::{
+    synth, Sound, BUILTIN_SAMPLE_RATE, BYTES_BOUNCE_BRICK, BYTES_BOUNCE_PADDLE,
+    BYTES_BOUNCE_PLAYFIELD_BORDER,
+};
+
+/// File extensions we know how to decode, in the order a sound pack is searched
+pub const EXTENSIONS: [&str; 2] = ["wav", "ogg"];
+
+/// Decoded sound, mixed down to mono
+#[derive(Clone, Debug, PartialEq)]
+pub struct SoundData {
+    pub samples: Vec<i16>,
+    pub sample_rate: i32,
+}
+impl SoundData {
+    /// Same sound, played back at `sample_rate`
+    pub fn resampled(&self, sample_rate: i32) -> SoundData {
+        SoundData {
+            samples: resample(&self.samples, self.sample_rate, sample_rate),
+            sample_rate,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum AssetError {
+    Io(PathBuf, io::Error),
+    Wav(PathBuf, hound::Error),
+    Ogg(PathBuf, lewton::VorbisError),
+    Unsupported(PathBuf),
+}
+impl fmt::Display for AssetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AssetError::Io(path, e) => write!(f, "unable to read {}: {}", path.display(), e),
+            AssetError::Wav(path, e) => write!(f, "unable to decode {}: {}", path.display(), e),
+            AssetError::Ogg(path, e) => write!(f, "unable to decode {}: {}", path.display(), e),
+            AssetError::Unsupported(path) => {
+                write!(f, "don't know how to decode {}", path.display())
+            }
+        }
+    }
+}
+impl Error for AssetError {}
+
+/// Every sound, from the sound pack if it has one, otherwise built in or synthesized, ready to
+/// play at the device's sample rate
+///
+/// Sound packs are directories of files named after [`Sound::name`], e.g. `bounce-brick.wav`.
+/// A pack doesn't need every sound, and a file that won't load is skipped with a warning.
+pub fn load_sounds(settings: &AudioSettings) -> HashMap<Sound, SoundData> {
+    let sound_pack = settings.sound_pack.as_deref().filter(|dir| {
+        let exists = dir.is_dir();
+        if !exists {
+            eprintln!(
+                "warning: there's no sound pack at {}, using the built in sounds instead",
+                dir.display()
+            );
+        }
+        exists
+    });
+
+    let mut sounds = HashMap::new();
+    for sound in Sound::ALL {
+        let from_pack = sound_pack
+            .and_then(|dir| find_in_pack(dir, sound))
+            .and_then(|path| match load_file(&path) {
+                Ok(data) => Some(data),
+                Err(e) => {
+                    eprintln!("warning: {}, using the built in sound instead", e);
+                    None
+                }
+            });
+        let data = from_pack.unwrap_or_else(|| match settings.sounds {
+            SoundSource::Samples => builtin(sound),
+            SoundSource::Synth => synth::synthesize(
+                match sound {
+                    Sound::BouncePaddle => &settings.synth.paddle,
+                    Sound::BouncePlayfieldBorder => &settings.synth.wall,
+                    Sound::BounceBrick => &settings.synth.brick,
+                },
+                settings.sample_rate,
+            ),
+        });
+
+        sounds.insert(sound, data.resampled(settings.sample_rate));
+    }
+    sounds
+}
+
+fn find_in_pack(dir: &Path, sound: Sound) -> Option<PathBuf> {
+    EXTENSIONS
+        .iter()
+        .map(|extension| dir.join(sound.name()).with_extension(extension))
+        .find(|path| path.is_file())
+}
+
+/// The sounds shipped inside the binary
+pub fn builtin(sound: Sound) -> SoundData {
+    let bytes = match sound {
+        Sound::BounceBrick => BYTES_BOUNCE_BRICK,
+        Sound::BouncePaddle => BYTES_BOUNCE_PADDLE,
+        Sound::BouncePlayfieldBorder => BYTES_BOUNCE_PLAYFIELD_BORDER,
+    };
+
+    // unsigned 8-bit stereo
+    let samples: Vec<i16> = bytes
+        .iter()
+        .map(|&sample| (sample as i16 - 128) << 8)
+        .collect();
+
+    SoundData {
+        samples: downmix(&samples, 2),
+        sample_rate: BUILTIN_SAMPLE_RATE,
+    }
+}
+
+/// Decode a sound file, going by its extension
+pub fn load_file(path: &Path) -> Result<SoundData, AssetError> {
+    let file = File::open(path).map_err(|e| AssetError::Io(path.to_owned(), e))?;
+    let reader = BufReader::new(file);
+
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some(extension) if extension.eq_ignore_ascii_case("wav") => {
+            decode_wav(reader).map_err(|e| AssetError::Wav(path.to_owned(), e))
+        }
+        Some(extension) if extension.eq_ignore_ascii_case("ogg") => {
+            decode_ogg(reader).map_err(|e| AssetError::Ogg(path.to_owned(), e))
+        }
+        _ => Err(AssetError::Unsupported(path.to_owned())),
+    }
+}
+
+/// Decode a WAV of any bit depth, integer or float, with any number of channels
+pub fn decode_wav(reader: impl Read) -> Result<SoundData, hound::Error> {
+    let mut wav = hound::WavReader::new(reader)?;
+    let spec = wav.spec();
+
+    let samples = match spec.sample_format {
+        hound::SampleFormat::Int => {
+            // scale everything to 16 bits
+            let shift = spec.bits_per_sample as i32 - 16;
+            wav.samples::<i32>()
+                .map(|sample| {
+                    sample.map(|sample| {
+                        if shift >= 0 {
+                            (sample >> shift) as i16
+                        } else {
+                            (sample << -shift) as i16
+                        }
+                    })
+                })
+                .collect::<Result<Vec<i16>, _>>()?
+        }
+        hound::SampleFormat::Float => wav
+            .samples::<f32>()
+            .map(|sample| sample.map(|sample| (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16))
+            .collect::<Result<Vec<i16>, _>>()?,
+    };
+
+    Ok(SoundData {
+        samples: downmix(&samples, spec.channels as usize),
+        sample_rate: spec.sample_rate as i32,
+    })
+}
+
+/// Decode an Ogg Vorbis stream
+pub fn decode_ogg(reader: impl Read + Seek) -> Result<SoundData, lewton::VorbisError> {
+    let mut ogg = lewton::inside_ogg::OggStreamReader::new(reader)?;
+    let channels = ogg.ident_hdr.audio_channels as usize;
+
+    let mut samples = Vec::new();
+    while let Some(packet) = ogg.read_dec_packet_itl()? {
+        samples.extend(packet);
+    }
+
+    Ok(SoundData {
+        samples: downmix(&samples, channels),
+        sample_rate: ogg.ident_hdr.audio_sample_rate as i32,
+    })
+}
+
+/// Average interleaved frames of `channels` samples down to one channel
+pub fn downmix(samples: &[i16], channels: usize) -> Vec<i16> {
+    if channels <= 1 {
+        return samples.to_vec();
+    }
+    samples
+        .chunks_exact(channels)
+        .map(|frame| (frame.iter().map(|&s| s as i32).sum::<i32>() / channels as i32) as i16)
+        .collect()
+}
+
+/// Linearly interpolate `samples` from one sample rate to another
+pub fn resample(samples: &[i16], from: i32, to: i32) -> Vec<i16> {
+    if from == to || samples.is_empty() {
+        return samples.to_vec();
+    }
+
+    let step = from as f64 / to as f64;
+    let len = (samples.len() as f64 / step).round() as usize;
+
+    (0..len)
+        .map(|i| {
+            let position = i as f64 * step;
+            let index = position as usize;
+            let fraction = position - index as f64;
+
+            let a = samples[index.min(samples.len() - 1)] as f64;
+            let b = samples[(index + 1).min(samples.len() - 1)] as f64;
+            (a + (b - a) * fraction).round() as i16
+        })
+        .collect()
+}
@@ -0,0 +1,83 @@
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use crate::view::audio::{AudioBackend, Sound, SoundEvent, Volume};
+
+/// A sound that would have been played
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct CapturedSound {
+    pub event: SoundEvent,
+    pub at: Instant,
+}
+
+/// Remembers which sounds were played, and when, instead of playing them
+///
+/// Clones share the same record, so keep one around to look at after handing the other over.
+#[derive(Clone, Default)]
+pub struct CapturingBackend {
+    captured: Arc<Mutex<Vec<CapturedSound>>>,
+    music: Arc<Mutex<Vec<Option<PathBuf>>>>,
+    volume: Arc<Mutex<Option<Volume>>>,
+}
+impl CapturingBackend {
+    pub fn new() -> CapturingBackend {
+        CapturingBackend::default()
+    }
+
+    /// Everything played so far, oldest first
+    pub fn captured(&self) -> Vec<CapturedSound> {
+        self.captured
+            .lock()
+            .expect("audio capture lock poisoned, exiting")
+            .clone()
+    }
+
+    /// Every music change so far, oldest first
+    pub fn music(&self) -> Vec<Option<PathBuf>> {
+        self.music
+            .lock()
+            .expect("audio capture lock poisoned, exiting")
+            .clone()
+    }
+
+    /// The last volume set, if any
+    pub fn volume(&self) -> Option<Volume> {
+        *self
+            .volume
+            .lock()
+            .expect("audio capture lock poisoned, exiting")
+    }
+
+    /// Just the sounds played so far, oldest first
+    pub fn sounds(&self) -> Vec<Sound> {
+        self.captured().iter().map(|c| c.event.sound).collect()
+    }
+}
+impl AudioBackend for CapturingBackend {
+    fn play(&mut self, event: SoundEvent) {
+        self.captured
+            .lock()
+            .expect("audio capture lock poisoned, exiting")
+            .push(CapturedSound {
+                event,
+                at: Instant::now(),
+            });
+    }
+
+    fn play_music(&mut self, track: Option<&Path>) {
+        self.music
+            .lock()
+            .expect("audio capture lock poisoned, exiting")
+            .push(track.map(Path::to_owned));
+    }
+
+    fn set_volume(&mut self, volume: Volume) {
+        *self
+            .volume
+            .lock()
+            .expect("audio capture lock poisoned, exiting") = Some(volume);
+    }
+
+    fn update(&mut self) {}
+}
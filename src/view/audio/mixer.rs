@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+use std::f32::consts::FRAC_PI_4;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::settings::Settings;
+use crate::view::audio::assets::{self, SoundData};
+use crate::view::audio::{AudioBackend, Sound, SoundEvent, Volume};
+
+struct Mix {
+    sounds: HashMap<Sound, SoundData>,
+    sample_rate: i32,
+    /// Where the next sound starts
+    time: Duration,
+    volume: Volume,
+    /// Interleaved left and right
+    samples: Vec<f32>,
+}
+
+/// Mixes sound effects down into stereo samples rather than playing them, for writing to a file
+///
+/// There's no music, and no limit on voices. Clones share the same mix, like
+/// [`CapturingBackend`](super::capture::CapturingBackend).
+#[derive(Clone)]
+pub struct MixingBackend {
+    mix: Arc<Mutex<Mix>>,
+}
+impl MixingBackend {
+    pub fn new(settings: &Settings) -> MixingBackend {
+        MixingBackend {
+            mix: Arc::new(Mutex::new(Mix {
+                sounds: assets::load_sounds(&settings.audio),
+                sample_rate: settings.audio.sample_rate,
+                time: Duration::ZERO,
+                volume: Volume::from(&settings.audio),
+                samples: Vec::new(),
+            })),
+        }
+    }
+
+    fn mix(&self) -> std::sync::MutexGuard<'_, Mix> {
+        self.mix.lock().expect("audio mix lock poisoned, exiting")
+    }
+
+    /// Sounds played from now on start `time` into the mix
+    pub fn set_time(&self, time: Duration) {
+        self.mix().time = time;
+    }
+
+    pub fn sample_rate(&self) -> i32 {
+        self.mix().sample_rate
+    }
+
+    /// Everything mixed so far, left and right interleaved
+    pub fn samples(&self) -> Vec<f32> {
+        self.mix().samples.clone()
+    }
+
+    /// Write the mix out as a 16-bit stereo WAV, padded with silence to `duration` so it lines
+    /// up with the video
+    pub fn save_wav(&self, path: &Path, duration: Duration) -> Result<(), hound::Error> {
+        let mix = self.mix();
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: mix.sample_rate as u32,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = hound::WavWriter::create(path, spec)?;
+        let length = mix
+            .samples
+            .len()
+            .max(frame_at(duration, mix.sample_rate) * 2);
+        for index in 0..length {
+            let sample = mix.samples.get(index).copied().unwrap_or(0.0);
+            writer.write_sample((sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16)?;
+        }
+        writer.finalize()
+    }
+}
+impl AudioBackend for MixingBackend {
+    fn play(&mut self, event: SoundEvent) {
+        let mut mix = self.mix();
+        let Some(data) = mix.sounds.get(&event.sound) else {
+            return;
+        };
+        let samples = resampled_by_pitch(&data.samples, event.pitch);
+        let (left, right) = pan_gains(event.pan);
+        // the master volume's for the speakers, files get the effects at full scale
+        let gain = mix.volume.effects / i16::MAX as f32;
+
+        let start = frame_at(mix.time, mix.sample_rate) * 2;
+        let end = start + samples.len() * 2;
+        if mix.samples.len() < end {
+            mix.samples.resize(end, 0.0);
+        }
+        for (frame, sample) in mix.samples[start..end].chunks_exact_mut(2).zip(samples) {
+            frame[0] += sample * gain * left;
+            frame[1] += sample * gain * right;
+        }
+    }
+
+    fn play_music(&mut self, _track: Option<&Path>) {}
+
+    fn set_volume(&mut self, volume: Volume) {
+        self.mix().volume = volume;
+    }
+
+    fn update(&mut self) {}
+}
+
+fn frame_at(time: Duration, sample_rate: i32) -> usize {
+    (time.as_secs_f64() * sample_rate as f64).round() as usize
+}
+
+/// Left and right gains for `pan` from -1 to 1, keeping the overall loudness the same
+pub fn pan_gains(pan: f32) -> (f32, f32) {
+    let angle = (pan.clamp(-1.0, 1.0) + 1.0) * FRAC_PI_4;
+    (angle.cos(), angle.sin())
+}
+
+/// Speed `samples` up by `pitch`, linearly interpolating between them, like OpenAL does
+fn resampled_by_pitch(samples: &[i16], pitch: f32) -> Vec<f32> {
+    if samples.is_empty() || pitch <= 0.0 {
+        return Vec::new();
+    }
+    let length = ((samples.len() - 1) as f32 / pitch) as usize + 1;
+    (0..length)
+        .map(|index| {
+            let position = index as f32 * pitch;
+            let before = position as usize;
+            let after = (before + 1).min(samples.len() - 1);
+            let fraction = position - before as f32;
+            samples[before] as f32 * (1.0 - fraction) + samples[after] as f32 * fraction
+        })
+        .collect()
+}
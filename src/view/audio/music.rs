@@ -0,0 +1,171 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::view::audio::assets::AssetError;
+
+/// Something music can be streamed out of, a chunk at a time
+pub trait MusicDecoder {
+    /// 1 or 2, anything more gets mixed down to mono
+    fn channels(&self) -> usize;
+    fn sample_rate(&self) -> i32;
+    /// Append up to `frames` frames of interleaved samples to `out`, returning how many frames
+    /// were read. Zero means the end of the track.
+    fn read(&mut self, out: &mut Vec<i16>, frames: usize) -> Result<usize, AssetError>;
+    /// Go back to the start of the track
+    fn rewind(&mut self) -> Result<(), AssetError>;
+}
+
+/// Open a music file for streaming, going by its extension
+pub fn open(path: &Path) -> Result<Box<dyn MusicDecoder>, AssetError> {
+    let file = File::open(path).map_err(|e| AssetError::Io(path.to_owned(), e))?;
+    let reader = BufReader::new(file);
+
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some(extension) if extension.eq_ignore_ascii_case("wav") => {
+            let wav =
+                hound::WavReader::new(reader).map_err(|e| AssetError::Wav(path.to_owned(), e))?;
+            Ok(Box::new(WavDecoder {
+                path: path.to_owned(),
+                wav,
+            }))
+        }
+        Some(extension) if extension.eq_ignore_ascii_case("ogg") => {
+            let ogg = lewton::inside_ogg::OggStreamReader::new(reader)
+                .map_err(|e| AssetError::Ogg(path.to_owned(), e))?;
+            Ok(Box::new(OggDecoder {
+                path: path.to_owned(),
+                ogg,
+                leftover: Vec::new(),
+            }))
+        }
+        _ => Err(AssetError::Unsupported(path.to_owned())),
+    }
+}
+
+struct WavDecoder {
+    path: PathBuf,
+    wav: hound::WavReader<BufReader<File>>,
+}
+impl MusicDecoder for WavDecoder {
+    fn channels(&self) -> usize {
+        self.wav.spec().channels as usize
+    }
+
+    fn sample_rate(&self) -> i32 {
+        self.wav.spec().sample_rate as i32
+    }
+
+    fn read(&mut self, out: &mut Vec<i16>, frames: usize) -> Result<usize, AssetError> {
+        let spec = self.wav.spec();
+        let wanted = frames * spec.channels as usize;
+        let before = out.len();
+
+        match spec.sample_format {
+            hound::SampleFormat::Int => {
+                let shift = spec.bits_per_sample as i32 - 16;
+                for sample in self.wav.samples::<i32>().take(wanted) {
+                    let sample = sample.map_err(|e| AssetError::Wav(self.path.clone(), e))?;
+                    out.push(if shift >= 0 {
+                        (sample >> shift) as i16
+                    } else {
+                        (sample << -shift) as i16
+                    });
+                }
+            }
+            hound::SampleFormat::Float => {
+                for sample in self.wav.samples::<f32>().take(wanted) {
+                    let sample = sample.map_err(|e| AssetError::Wav(self.path.clone(), e))?;
+                    out.push((sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16);
+                }
+            }
+        }
+
+        Ok((out.len() - before) / spec.channels as usize)
+    }
+
+    fn rewind(&mut self) -> Result<(), AssetError> {
+        self.wav
+            .seek(0)
+            .map_err(|e| AssetError::Io(self.path.clone(), e))
+    }
+}
+
+struct OggDecoder {
+    path: PathBuf,
+    ogg: lewton::inside_ogg::OggStreamReader<BufReader<File>>,
+    /// Decoded, but didn't fit in the last read
+    leftover: Vec<i16>,
+}
+impl MusicDecoder for OggDecoder {
+    fn channels(&self) -> usize {
+        self.ogg.ident_hdr.audio_channels as usize
+    }
+
+    fn sample_rate(&self) -> i32 {
+        self.ogg.ident_hdr.audio_sample_rate as i32
+    }
+
+    fn read(&mut self, out: &mut Vec<i16>, frames: usize) -> Result<usize, AssetError> {
+        let channels = self.channels();
+        let wanted = frames * channels;
+
+        while self.leftover.len() < wanted {
+            match self
+                .ogg
+                .read_dec_packet_itl()
+                .map_err(|e| AssetError::Ogg(self.path.clone(), e))?
+            {
+                Some(packet) => self.leftover.extend(packet),
+                None => break,
+            }
+        }
+
+        let taken = wanted.min(self.leftover.len());
+        out.extend(self.leftover.drain(..taken));
+        Ok(taken / channels)
+    }
+
+    fn rewind(&mut self) -> Result<(), AssetError> {
+        self.leftover.clear();
+        self.ogg
+            .seek_absgp_pg(0)
+            .map_err(|e| AssetError::Ogg(self.path.clone(), e))
+    }
+}
+
+/// A gain that slides from one level to another over time
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Fader {
+    from: f32,
+    to: f32,
+    duration: Duration,
+    elapsed: Duration,
+}
+impl Fader {
+    pub fn new(from: f32, to: f32, duration: Duration) -> Fader {
+        Fader {
+            from,
+            to,
+            duration,
+            elapsed: Duration::ZERO,
+        }
+    }
+
+    pub fn advance(&mut self, delta_t: Duration) {
+        self.elapsed = (self.elapsed + delta_t).min(self.duration);
+    }
+
+    pub fn gain(&self) -> f32 {
+        if self.done() {
+            return self.to;
+        }
+        let progress = self.elapsed.as_secs_f32() / self.duration.as_secs_f32();
+        self.from + (self.to - self.from) * progress
+    }
+
+    pub fn done(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+}
@@ -0,0 +1,259 @@
+use alto::{
+    Alto, AltoError, Buffer, Context, Mono, Source, SourceState, StaticSource, Stereo,
+    StreamingSource,
+};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::console;
+use crate::settings::Settings;
+use crate::view::audio::{
+    assets::{self, AssetError},
+    music::{self, Fader, MusicDecoder},
+    voices::VoicePool,
+    AudioBackend, Sound, SoundEvent, Volume,
+};
+
+/// How many buffers each music track keeps queued up
+const MUSIC_BUFFERS: usize = 4;
+/// How much music goes in each buffer, a little under 200ms at 44.1kHz
+const MUSIC_CHUNK_FRAMES: usize = 8_192;
+
+/// Plays sounds through the default OpenAL device, several at a time, with music underneath
+pub struct OpenAlBackend {
+    al_context: Context,
+    al_sources: Vec<StaticSource>,
+    buffers: HashMap<Sound, Arc<Buffer>>,
+    voices: VoicePool,
+
+    /// The last one is the current track, any others are fading out
+    music: Vec<MusicVoice>,
+    crossfade: Duration,
+    last_update: Option<Instant>,
+
+    volume: Volume,
+}
+impl OpenAlBackend {
+    pub fn new(settings: &Settings) -> Result<OpenAlBackend, AltoError> {
+        // set up openal
+        let alto = Alto::load_default()?;
+        let al_device = alto.open(None)?;
+
+        let al_context = al_device.new_context(None)?;
+        let volume = Volume::from(&settings.audio);
+        al_context.set_gain(volume.output())?;
+
+        // every sound gets uploaded once, up front, and in mono so openal can pan it
+        let mut buffers = HashMap::new();
+        for (sound, data) in assets::load_sounds(&settings.audio) {
+            let buffer = al_context.new_buffer::<Mono<i16>, _>(data.samples, data.sample_rate)?;
+            buffers.insert(sound, Arc::new(buffer));
+        }
+
+        let mut al_sources = Vec::new();
+        for _ in 0..settings.audio.max_voices {
+            let mut al_source = al_context.new_static_source()?;
+            // positions are relative to the listener, who's always in the middle
+            al_source.set_relative(true);
+            al_sources.push(al_source);
+        }
+
+        Ok(OpenAlBackend {
+            al_context,
+            voices: VoicePool::new(al_sources.len()),
+            al_sources,
+            buffers,
+
+            music: Vec::new(),
+            crossfade: Duration::from_secs_f32(settings.audio.music.crossfade),
+            last_update: None,
+
+            volume,
+        })
+    }
+}
+impl AudioBackend for OpenAlBackend {
+    fn play(&mut self, event: SoundEvent) {
+        for (index, al_source) in self.al_sources.iter().enumerate() {
+            if al_source.state() != SourceState::Playing {
+                self.voices.finished(index);
+            }
+        }
+
+        let Some(index) = self.voices.allocate(event.sound) else {
+            // everything playing is more important
+            return;
+        };
+
+        let al_source = &mut self.al_sources[index];
+        al_source.stop();
+        al_source
+            .set_buffer(self.buffers[&event.sound].clone())
+            .expect("unable to set openal buffer, exiting");
+        al_source
+            .set_position(pan_to_position(event.pan))
+            .expect("unable to set openal source position, exiting");
+        al_source
+            .set_pitch(event.pitch)
+            .expect("unable to set openal source pitch, exiting");
+        al_source
+            .set_gain(self.volume.effects)
+            .expect("unable to set openal source gain, exiting");
+        al_source.play();
+    }
+
+    fn play_music(&mut self, track: Option<&Path>) {
+        for voice in self.music.iter_mut() {
+            voice.fader = Fader::new(voice.fader.gain(), 0.0, self.crossfade);
+        }
+
+        let Some(track) = track else {
+            return;
+        };
+        match MusicVoice::new(&self.al_context, track, self.crossfade) {
+            Ok(voice) => self.music.push(voice),
+            Err(e) => console::warn(format!("unable to play music ({}), skipping it", e)),
+        }
+    }
+
+    fn set_volume(&mut self, volume: Volume) {
+        self.al_context
+            .set_gain(volume.output())
+            .expect("unable to set openal context gain, exiting");
+        // effects already playing keep their volume, music catches up on the next update
+        self.volume = volume;
+    }
+
+    fn update(&mut self) {
+        let now = Instant::now();
+        let delta_t = match self.last_update {
+            Some(then) => now.duration_since(then),
+            None => Duration::ZERO,
+        };
+        self.last_update = Some(now);
+
+        for voice in self.music.iter_mut() {
+            voice.update(delta_t, self.volume.music);
+        }
+
+        // faded out for good
+        self.music
+            .retain(|voice| !(voice.fader.done() && voice.fader.gain() == 0.0));
+    }
+}
+
+/// One music track streaming through its own source
+struct MusicVoice {
+    al_source: StreamingSource,
+    decoder: Box<dyn MusicDecoder>,
+    fader: Fader,
+}
+impl MusicVoice {
+    fn new(al_context: &Context, track: &Path, fade_in: Duration) -> Result<MusicVoice, String> {
+        let decoder = music::open(track).map_err(|e| e.to_string())?;
+        let al_source = al_context
+            .new_streaming_source()
+            .map_err(|e| e.to_string())?;
+
+        let mut voice = MusicVoice {
+            al_source,
+            decoder,
+            fader: Fader::new(0.0, 1.0, fade_in),
+        };
+        voice.al_source.set_gain(0.0).map_err(|e| e.to_string())?;
+
+        for _ in 0..MUSIC_BUFFERS {
+            let samples = voice.next_chunk().map_err(|e| e.to_string())?;
+            let buffer = voice
+                .new_buffer(al_context, &samples)
+                .map_err(|e| e.to_string())?;
+            voice
+                .al_source
+                .queue_buffer(buffer)
+                .map_err(|e| e.to_string())?;
+        }
+        voice.al_source.play();
+
+        Ok(voice)
+    }
+
+    fn update(&mut self, delta_t: Duration, volume: f32) {
+        self.fader.advance(delta_t);
+        self.al_source
+            .set_gain(self.fader.gain() * volume)
+            .expect("unable to set openal source gain, exiting");
+
+        // refill whatever's been played
+        for _ in 0..self.al_source.buffers_processed() {
+            let mut buffer = self
+                .al_source
+                .unqueue_buffer()
+                .expect("unable to unqueue openal buffer, exiting");
+
+            let samples = match self.next_chunk() {
+                Ok(samples) => samples,
+                Err(e) => {
+                    console::warn(format!("music stopped ({})", e));
+                    self.fader = Fader::new(0.0, 0.0, Duration::ZERO);
+                    return;
+                }
+            };
+            self.set_buffer_data(&mut buffer, &samples)
+                .expect("unable to fill openal buffer, exiting");
+            self.al_source
+                .queue_buffer(buffer)
+                .expect("unable to queue openal buffer, exiting");
+        }
+
+        // ran dry before we got to it, e.g. the window was being dragged
+        if self.al_source.state() != SourceState::Playing {
+            self.al_source.play();
+        }
+    }
+
+    /// The next bit of the track, going back to the start at the end
+    fn next_chunk(&mut self) -> Result<Vec<i16>, AssetError> {
+        let mut samples = Vec::new();
+        let mut frames = self.decoder.read(&mut samples, MUSIC_CHUNK_FRAMES)?;
+        if frames == 0 {
+            self.decoder.rewind()?;
+            frames = self.decoder.read(&mut samples, MUSIC_CHUNK_FRAMES)?;
+        }
+        if frames == 0 {
+            // empty track, loop silence rather than spin
+            samples = vec![0; MUSIC_CHUNK_FRAMES * self.decoder.channels()];
+        }
+
+        // openal only does mono and stereo
+        if self.decoder.channels() > 2 {
+            samples = assets::downmix(&samples, self.decoder.channels());
+        }
+        Ok(samples)
+    }
+
+    fn new_buffer(&self, al_context: &Context, samples: &[i16]) -> Result<Buffer, AltoError> {
+        let sample_rate = self.decoder.sample_rate();
+        if self.decoder.channels() == 2 {
+            al_context.new_buffer::<Stereo<i16>, _>(samples, sample_rate)
+        } else {
+            al_context.new_buffer::<Mono<i16>, _>(samples, sample_rate)
+        }
+    }
+
+    fn set_buffer_data(&self, buffer: &mut Buffer, samples: &[i16]) -> Result<(), AltoError> {
+        let sample_rate = self.decoder.sample_rate();
+        if self.decoder.channels() == 2 {
+            buffer.set_data::<Stereo<i16>, _>(samples, sample_rate)
+        } else {
+            buffer.set_data::<Mono<i16>, _>(samples, sample_rate)
+        }
+    }
+}
+
+/// Put a sound on a unit circle in front of the listener, so it's equally loud wherever it's panned
+fn pan_to_position(pan: f32) -> [f32; 3] {
+    let pan = pan.clamp(-1.0, 1.0);
+    [pan, 0.0, -(1.0 - pan * pan).sqrt()]
+}
@@ -0,0 +1,167 @@
+use serde::Deserialize;
+
+use crate::logic::rng::Rng;
+use crate::view::audio::assets::SoundData;
+
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Waveform {
+    Square,
+    Triangle,
+    Noise,
+}
+
+/// Attack, decay, sustain, release. Times are in seconds, `sustain` is a level from 0 to 1.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Envelope {
+    pub attack: f32,
+    pub decay: f32,
+    pub sustain: f32,
+    /// How long the sustain level is held for
+    pub hold: f32,
+    pub release: f32,
+}
+impl Default for Envelope {
+    fn default() -> Envelope {
+        Envelope {
+            attack: 0.002,
+            decay: 0.02,
+            sustain: 0.5,
+            hold: 0.03,
+            release: 0.05,
+        }
+    }
+}
+impl Envelope {
+    pub fn duration(&self) -> f32 {
+        self.attack + self.decay + self.hold + self.release
+    }
+
+    /// Loudness `t` seconds in
+    pub fn level(&self, t: f32) -> f32 {
+        let mut t = t;
+        if t < self.attack {
+            return t / self.attack;
+        }
+        t -= self.attack;
+        if t < self.decay {
+            return 1.0 - (1.0 - self.sustain) * t / self.decay;
+        }
+        t -= self.decay;
+        if t < self.hold {
+            return self.sustain;
+        }
+        t -= self.hold;
+        if t < self.release {
+            return self.sustain * (1.0 - t / self.release);
+        }
+        0.0
+    }
+}
+
+/// Everything needed to make one sound
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SynthParams {
+    pub waveform: Waveform,
+    /// Starting pitch, in Hz
+    pub frequency: f32,
+    /// Pitch at the very end, swept to exponentially from `frequency`
+    pub frequency_end: f32,
+    /// Fraction of each period a square wave is high
+    pub duty: f32,
+    pub volume: f32,
+    pub envelope: Envelope,
+}
+impl Default for SynthParams {
+    fn default() -> SynthParams {
+        SynthParams {
+            waveform: Waveform::Square,
+            frequency: 440.0,
+            frequency_end: 440.0,
+            duty: 0.5,
+            volume: 0.5,
+            envelope: Envelope::default(),
+        }
+    }
+}
+impl SynthParams {
+    pub fn paddle() -> SynthParams {
+        SynthParams {
+            frequency: 330.0,
+            frequency_end: 660.0,
+            ..SynthParams::default()
+        }
+    }
+
+    pub fn wall() -> SynthParams {
+        SynthParams {
+            waveform: Waveform::Triangle,
+            frequency: 220.0,
+            frequency_end: 180.0,
+            volume: 0.7,
+            ..SynthParams::default()
+        }
+    }
+
+    pub fn brick() -> SynthParams {
+        SynthParams {
+            frequency: 880.0,
+            frequency_end: 440.0,
+            duty: 0.25,
+            envelope: Envelope {
+                hold: 0.01,
+                release: 0.08,
+                ..Envelope::default()
+            },
+            ..SynthParams::default()
+        }
+    }
+}
+
+/// Render `params` to samples
+pub fn synthesize(params: &SynthParams, sample_rate: i32) -> SoundData {
+    let duration = params.envelope.duration();
+    let len = (duration * sample_rate as f32).ceil() as usize;
+
+    // noise is the same every time, so a config always sounds the same
+    let mut rng = Rng::new(0x5eed);
+    let mut noise = 0.0;
+
+    let mut phase = 0.0_f32;
+    let mut samples = Vec::with_capacity(len);
+    for i in 0..len {
+        let t = i as f32 / sample_rate as f32;
+
+        let sweep = if duration > 0.0 { t / duration } else { 0.0 };
+        let frequency = params.frequency * (params.frequency_end / params.frequency).powf(sweep);
+
+        let value = match params.waveform {
+            Waveform::Square => {
+                if phase < params.duty {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+            Waveform::Triangle => 1.0 - 4.0 * (phase - 0.5).abs(),
+            Waveform::Noise => noise,
+        };
+
+        let level = params.envelope.level(t) * params.volume;
+        samples.push((value * level * i16::MAX as f32) as i16);
+
+        phase += frequency / sample_rate as f32;
+        if phase >= 1.0 {
+            phase -= 1.0;
+            // new noise value every period, so noise still has a pitch
+            noise = rng.next_f32() * 2.0 - 1.0;
+        }
+    }
+
+    SoundData {
+        samples,
+        sample_rate,
+    }
+}
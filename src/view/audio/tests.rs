@@ -0,0 +1,278 @@
+use crate::logic::interaction::{Bounce, Impact};
+use crate::logic::Phase;
+use crate::settings::Settings;
+use crate::view::audio::capture::CapturingBackend;
+use crate::view::audio::mixer::{pan_gains, MixingBackend};
+use crate::view::audio::{
+    assets, music::Fader, open_backend, synth, voices::VoicePool, AudioBackend, AudioState, Sound,
+    SoundEvent, Volume, SAMPLE_RATE,
+};
+use std::io::Cursor;
+use std::path::PathBuf;
+use std::time::Duration;
+
+fn impact(bounce: Bounce, x: f32, y: f32) -> Impact {
+    Impact { bounce, x, y }
+}
+
+#[test]
+fn bounces_play_matching_sounds() {
+    let (mut audio_state, capture) = AudioState::capturing(&Settings::default());
+
+    audio_state.on_impact(impact(Bounce::Paddle, 0.0, -0.8));
+    audio_state.on_impact(impact(Bounce::Brick, 0.0, 0.5));
+    audio_state.on_impact(impact(Bounce::PlayfieldBorder, 1.0, 0.0));
+    audio_state.on_impact(impact(Bounce::Brick, 0.0, 0.5));
+
+    assert_eq!(
+        capture.sounds(),
+        [
+            Sound::BouncePaddle,
+            Sound::BounceBrick,
+            Sound::BouncePlayfieldBorder,
+            Sound::BounceBrick,
+        ]
+    );
+
+    let captured = capture.captured();
+    assert!(captured.windows(2).all(|pair| pair[0].at <= pair[1].at));
+}
+
+#[test]
+fn impacts_are_panned_and_bricks_pitched() {
+    let (mut audio_state, capture) = AudioState::capturing(&Settings::default());
+
+    audio_state.on_impact(impact(Bounce::PlayfieldBorder, -1.0, 0.3));
+    audio_state.on_impact(impact(Bounce::Paddle, 0.5, -0.8));
+    audio_state.on_impact(impact(Bounce::Brick, 0.0, 0.1));
+    audio_state.on_impact(impact(Bounce::Brick, 0.0, 0.9));
+
+    let events: Vec<_> = capture.captured().iter().map(|c| c.event).collect();
+    assert_eq!(events[0].pan, -1.0);
+    assert_eq!(events[1].pan, 0.5);
+    assert_eq!(events[1].pitch, 1.0);
+    assert!(events[3].pitch > events[2].pitch);
+
+    let mut settings = Settings::default();
+    settings.audio.panning = false;
+    settings.audio.pitch_bricks_by_row = false;
+    let (mut audio_state, capture) = AudioState::capturing(&settings);
+
+    audio_state.on_impact(impact(Bounce::Brick, 0.7, 0.9));
+    assert_eq!(capture.captured()[0].event.pan, 0.0);
+    assert_eq!(capture.captured()[0].event.pitch, 1.0);
+}
+
+#[test]
+fn voices_are_stolen_by_priority() {
+    let mut voices = VoicePool::new(2);
+
+    assert_eq!(voices.allocate(Sound::BounceBrick), Some(0));
+    assert_eq!(voices.allocate(Sound::BouncePaddle), Some(1));
+
+    // the brick is the least important thing playing, so it goes
+    assert_eq!(voices.allocate(Sound::BounceBrick), Some(0));
+    // a wall bounce isn't worth interrupting anything for
+    assert_eq!(voices.allocate(Sound::BouncePlayfieldBorder), None);
+    // the oldest of equally important voices goes first
+    assert_eq!(voices.allocate(Sound::BouncePaddle), Some(0));
+    assert_eq!(voices.allocate(Sound::BouncePaddle), Some(1));
+
+    // idle voices are used before stealing any
+    voices.finished(0);
+    assert_eq!(voices.allocate(Sound::BouncePlayfieldBorder), Some(0));
+    assert_eq!(
+        voices.playing().collect::<Vec<_>>(),
+        [
+            Some(Sound::BouncePlayfieldBorder),
+            Some(Sound::BouncePaddle)
+        ]
+    );
+}
+
+#[test]
+fn wavs_are_mixed_down_and_resampled() {
+    // a stereo, 24-bit, 22.05 kHz ramp
+    let mut wav = Cursor::new(Vec::new());
+    let spec = hound::WavSpec {
+        channels: 2,
+        sample_rate: 22_050,
+        bits_per_sample: 24,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut writer = hound::WavWriter::new(&mut wav, spec).unwrap();
+    for i in 0..100 {
+        writer.write_sample(i * 256).unwrap();
+        writer.write_sample(i * 256 + 512).unwrap();
+    }
+    writer.finalize().unwrap();
+    wav.set_position(0);
+
+    let data = assets::decode_wav(wav).unwrap();
+    assert_eq!(data.sample_rate, 22_050);
+    assert_eq!(data.samples.len(), 100);
+    assert_eq!(data.samples[10], 11);
+
+    let data = data.resampled(44_100);
+    assert_eq!(data.sample_rate, 44_100);
+    assert_eq!(data.samples.len(), 200);
+    assert_eq!(data.samples[20], 11);
+    // halfway between two of the originals
+    assert_eq!(data.samples[21], 12);
+}
+
+#[test]
+fn builtin_sounds_load() {
+    for sound in Sound::ALL {
+        let data = assets::builtin(sound).resampled(SAMPLE_RATE);
+        assert!(!data.samples.is_empty());
+        assert_eq!(data.sample_rate, SAMPLE_RATE);
+    }
+}
+
+#[test]
+fn missing_sound_packs_fall_back_to_builtin_sounds() {
+    let mut settings = Settings::default();
+    settings.audio.sound_pack = Some(PathBuf::from("no/such/sound/pack"));
+    let sounds = assets::load_sounds(&settings.audio);
+    for sound in Sound::ALL {
+        assert_eq!(
+            sounds[&sound],
+            assets::builtin(sound).resampled(settings.audio.sample_rate)
+        );
+    }
+}
+
+#[test]
+fn synth_follows_envelope() {
+    let params = synth::SynthParams::brick();
+    let data = synth::synthesize(&params, 10_000);
+
+    let expected_len = (params.envelope.duration() * 10_000.0).ceil() as usize;
+    assert_eq!(data.samples.len(), expected_len);
+
+    // loud near the start, silent at the very end
+    let peak = data.samples[..100].iter().map(|s| s.unsigned_abs()).max();
+    assert!(peak > Some(i16::MAX as u16 / 4));
+    assert!(data.samples.last().unwrap().unsigned_abs() < 100);
+}
+
+#[test]
+fn music_follows_the_game() {
+    let mut settings = Settings::default();
+    settings.audio.music.menu = Some(PathBuf::from("menu.ogg"));
+    settings.audio.music.play = vec![PathBuf::from("one.ogg"), PathBuf::from("two.wav")];
+    let (audio_state, _) = AudioState::capturing(&settings);
+
+    assert_eq!(
+        audio_state.track_for(Phase::Menu, 1),
+        Some(PathBuf::from("menu.ogg"))
+    );
+    assert_eq!(
+        audio_state.track_for(Phase::Playing, 1),
+        Some(PathBuf::from("one.ogg"))
+    );
+    assert_eq!(
+        audio_state.track_for(Phase::Playing, 5),
+        Some(PathBuf::from("two.wav"))
+    );
+    assert_eq!(audio_state.track_for(Phase::GameOver, 1), None);
+}
+
+#[test]
+fn faders_ramp_linearly() {
+    let mut fader = Fader::new(1.0, 0.0, Duration::from_secs(2));
+    assert_eq!(fader.gain(), 1.0);
+
+    fader.advance(Duration::from_millis(500));
+    assert_eq!(fader.gain(), 0.75);
+    assert!(!fader.done());
+
+    fader.advance(Duration::from_secs(5));
+    assert_eq!(fader.gain(), 0.0);
+    assert!(fader.done());
+
+    let instant = Fader::new(0.0, 1.0, Duration::ZERO);
+    assert_eq!(instant.gain(), 1.0);
+}
+
+#[test]
+fn music_is_ducked_while_paused() {
+    let volume = Volume {
+        master: 0.5,
+        effects: 1.0,
+        music: 0.5,
+        muted: false,
+    };
+
+    assert_eq!(volume.ducked(false), volume);
+    assert!(volume.ducked(true).music < volume.music);
+    assert_eq!(volume.ducked(true).effects, volume.effects);
+
+    assert_eq!(volume.output(), 0.5);
+    let muted = Volume {
+        muted: true,
+        ..volume
+    };
+    assert_eq!(muted.output(), 0.0);
+    assert_eq!(
+        toml::from_str::<Volume>(&toml::to_string(&muted).unwrap()).unwrap(),
+        muted
+    );
+}
+
+#[test]
+fn mixed_sounds_are_panned_and_placed_in_time() {
+    let (left, right) = pan_gains(0.0);
+    assert!((left - right).abs() < 1e-6);
+    assert!((left * left + right * right - 1.0).abs() < 1e-6);
+    assert!(pan_gains(-1.0).1.abs() < 1e-6);
+
+    let mut mixer = MixingBackend::new(&Settings::default());
+    mixer.set_time(Duration::from_millis(100));
+    mixer.play(SoundEvent {
+        sound: Sound::BouncePaddle,
+        pan: 1.0,
+        pitch: 1.0,
+    });
+
+    let samples = mixer.samples();
+    let start = (0.1 * mixer.sample_rate() as f32) as usize * 2;
+    assert!(samples[..start].iter().all(|&sample| sample == 0.0));
+    let (left, right): (Vec<f32>, Vec<f32>) = samples[start..]
+        .chunks_exact(2)
+        .map(|frame| (frame[0], frame[1]))
+        .unzip();
+    assert!(left.iter().all(|sample| sample.abs() < 1e-6));
+    assert!(right.iter().any(|&sample| sample != 0.0));
+}
+
+#[test]
+fn missing_audio_falls_back_to_silence() {
+    let opened = std::cell::Cell::new(false);
+    let open = |result: Result<(), &'static str>| {
+        opened.set(true);
+        result.map(|()| Box::new(CapturingBackend::new()) as Box<dyn AudioBackend>)
+    };
+
+    // turned off, so there's no trying
+    let mut settings = Settings::default();
+    settings.audio.enabled = false;
+    let mut backend = open_backend(&settings, || open(Ok(())));
+    assert!(!opened.get());
+    backend.play(SoundEvent::centered(Sound::BouncePaddle));
+
+    // failing to open isn't fatal
+    let settings = Settings::default();
+    let mut backend = open_backend(&settings, || open(Err("no device")));
+    assert!(opened.get());
+    backend.play(SoundEvent::centered(Sound::BouncePaddle));
+    backend.update();
+
+    let capture = CapturingBackend::new();
+    let mut backend = open_backend(&settings, || {
+        Ok::<_, &str>(Box::new(capture.clone()) as Box<dyn AudioBackend>)
+    });
+    backend.play(SoundEvent::centered(Sound::BouncePaddle));
+    assert_eq!(capture.sounds(), [Sound::BouncePaddle]);
+}
@@ -0,0 +1,72 @@
+use crate::view::audio::Sound;
+
+/// Keeps track of which voices are busy, and decides which one a new sound gets
+///
+/// Doesn't play anything itself, backends map voice indices onto their own sources and report
+/// back with [`VoicePool::finished`] once a voice goes quiet.
+#[derive(Clone, Debug)]
+pub struct VoicePool {
+    voices: Vec<Option<Voice>>,
+    /// Counts up with every sound started, so we know which voices are oldest
+    started: u64,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Voice {
+    sound: Sound,
+    priority: u32,
+    started: u64,
+}
+
+impl VoicePool {
+    pub fn new(max_voices: usize) -> VoicePool {
+        VoicePool {
+            voices: vec![None; max_voices],
+            started: 0,
+        }
+    }
+
+    /// Pick a voice for `sound`, or `None` if every voice is busy with something more important
+    ///
+    /// Prefers idle voices, otherwise steals the oldest of the lowest priority voices, as long as
+    /// it's no more important than `sound`.
+    pub fn allocate(&mut self, sound: Sound) -> Option<usize> {
+        let priority = sound.priority();
+
+        let index = match self.voices.iter().position(|voice| voice.is_none()) {
+            Some(index) => index,
+            None => {
+                let (index, victim) = self
+                    .voices
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(index, voice)| voice.map(|voice| (index, voice)))
+                    .min_by_key(|(_, voice)| (voice.priority, voice.started))?;
+                if victim.priority > priority {
+                    return None;
+                }
+                index
+            }
+        };
+
+        self.started += 1;
+        self.voices[index] = Some(Voice {
+            sound,
+            priority,
+            started: self.started,
+        });
+        Some(index)
+    }
+
+    /// Mark a voice as idle
+    pub fn finished(&mut self, index: usize) {
+        self.voices[index] = None;
+    }
+
+    /// What each voice is playing, if anything
+    pub fn playing(&self) -> impl Iterator<Item = Option<Sound>> + '_ {
+        self.voices
+            .iter()
+            .map(|voice| voice.map(|voice| voice.sound))
+    }
+}
@@ -1,4 +1,25 @@
-use crate::view::video::{quad, Vertex};
+use crate::logic::game_objs::{GameObject, GameObjectKind};
+use crate::logic::LogicState;
+use crate::settings::{PostEffect, PostSettings, Settings};
+use crate::stats::FrameStats;
+use crate::view::video::atlas::{decode_png, encode_png, Atlas, AtlasError, Image, SpriteSheet};
+use crate::view::video::batch::brick_instances;
+use crate::view::video::debug::{DebugOverlay, LINE_WIDTH, TOO_LATE_COLOR};
+use crate::view::video::draw_list::{CacheKey, DrawCommand, DrawList, Text};
+use crate::view::video::particles::{Particles, SPARK_COLOR};
+use crate::view::video::scene::Scene;
+use crate::view::video::screenshot;
+use crate::view::video::software::SoftwareRenderer;
+use crate::view::video::terminal::cells;
+use crate::view::video::theme::{Theme, ThemeError};
+use crate::view::video::Drawable;
+use crate::view::video::{camera::Camera, quad, trail::Trail, Vertex};
+use crate::view::video::{pause_overlay, post, BlendedVertex, Renderer, VideoState};
+use std::cell::RefCell;
+use std::fs::File;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
 
 #[test]
 fn quad_scaling() {
@@ -33,3 +54,595 @@ fn quad_scaling() {
         ]
     );
 }
+
+#[test]
+fn particles_expire_and_stay_capped() {
+    let brick = GameObject::brick(0.0, 0.5, 0.1, 0.06, [0.5, 0.0, 0.1]);
+
+    let mut particles = Particles::new(10);
+    particles.spawn_debris(&brick, [0.5, 0.0, 0.1]);
+    assert_eq!(particles.len(), 8);
+    assert!(particles
+        .vertices()
+        .iter()
+        .all(|vertex| vertex.color == [0.5, 0.0, 0.1]));
+
+    // sparks push out the oldest debris rather than going over the limit
+    particles.spawn_sparks(1.0, 0.0, SPARK_COLOR);
+    assert_eq!(particles.len(), 10);
+
+    particles.update(0.1);
+    assert!(!particles.is_empty());
+    particles.update(10.0);
+    assert!(particles.is_empty());
+}
+
+#[test]
+fn trail_fades_and_resets_on_respawn() {
+    let settings = Settings::default();
+    let ball = GameObject::ball(settings.logic.balls, 0.0, 1.0, &settings.logic);
+    assert_eq!(ball.kind, GameObjectKind::Ball);
+
+    let mut trail = Trail::new(4);
+    for i in 0..6 {
+        trail.record(i as f32 * 0.01, 0.0);
+    }
+    assert_eq!(trail.len(), 4);
+
+    // newest is the ball itself, the rest get a quad each, fading with age
+    let vertices = trail.vertices(&ball, ball.model[0].color);
+    assert_eq!(vertices.len(), 3 * 6);
+    let alphas: Vec<f32> = vertices.chunks(6).map(|quad| quad[0].color[3]).collect();
+    assert!(alphas.windows(2).all(|pair| pair[0] > pair[1]));
+    assert!(vertices
+        .iter()
+        .all(|vertex| vertex.color[..3] == ball.model[0].color));
+
+    trail.record(0.0, -0.9);
+    assert_eq!(trail.len(), 1);
+}
+
+#[test]
+fn shake_settles_and_can_be_turned_off() {
+    let mut camera = Camera::new(1.0);
+    camera.add_trauma(1.0);
+    camera.update(0.01);
+    assert_ne!(camera.offset(), (0.0, 0.0));
+
+    // trauma wears off
+    camera.update(1.0);
+    assert_eq!(camera.offset(), (0.0, 0.0));
+
+    let mut camera = Camera::new(0.0);
+    camera.add_trauma(1.0);
+    camera.update(0.01);
+    assert_eq!(camera.offset(), (0.0, 0.0));
+    assert_eq!(camera.matrix()[0][0], 1.0);
+}
+
+#[test]
+fn sprites_come_from_the_atlas_or_fall_back() {
+    // 4x2, rgb, so it has to be expanded to rgba
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, 4, 2);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder
+        .write_header()
+        .unwrap()
+        .write_image_data(&[255; 4 * 2 * 3])
+        .unwrap();
+    let image = decode_png(&png[..]).unwrap();
+    assert_eq!((image.width, image.height), (4, 2));
+    assert_eq!(image.pixels, vec![255; 4 * 2 * 4]);
+
+    let sheet: SpriteSheet = toml::from_str(
+        "
+        image = 'sprites.png'
+        [sprites]
+        brick = [2, 0, 2, 1]
+        ",
+    )
+    .unwrap();
+    let atlas = Atlas::new(image.clone(), &sheet).unwrap();
+
+    let brick = GameObject::brick(0.5, 0.5, 0.1, 0.06, [0.5, 0.0, 0.1]);
+    let vertices = brick.get_textured_vertices(&atlas).unwrap();
+    assert_eq!(vertices.len(), 6);
+    // top right corner of the brick is the top right of the image
+    assert_eq!(vertices[0].tex_coords, [1.0, 1.0]);
+    assert_eq!(vertices[2].tex_coords, [0.5, 0.5]);
+
+    // nothing in the atlas for the paddle, so it stays flat
+    let paddle = GameObject::paddle(&Default::default());
+    assert!(paddle.get_textured_vertices(&atlas).is_none());
+
+    let sheet: SpriteSheet = toml::from_str(
+        "
+        image = 'sprites.png'
+        [sprites]
+        ball = [3, 1, 2, 2]
+        ",
+    )
+    .unwrap();
+    assert!(matches!(
+        Atlas::new(image, &sheet),
+        Err(AtlasError::OutOfBounds(_))
+    ));
+}
+
+#[test]
+fn instances_match_rebuilt_vertices() {
+    let bricks = [
+        GameObject::brick(0.0, 0.5, 0.1, 0.06, [0.5, 0.0, 0.1]),
+        GameObject::brick(0.2, 0.7, 0.1, 0.06, [0.0, 0.5, 0.1]),
+    ];
+    let instances = brick_instances(bricks.iter().map(|brick| (brick, brick.model[0].color)));
+    for (instance, brick) in instances.iter().zip(bricks.iter()) {
+        // the unit quad, scaled and moved, lands where the brick's own vertices do
+        let instanced = quad(1.0, 1.0, instance.color).map(|mut vertex| {
+            vertex.position[0] = vertex.position[0] * instance.size[0] + instance.offset[0];
+            vertex.position[1] = vertex.position[1] * instance.size[1] + instance.offset[1];
+            vertex
+        });
+        assert_eq!(instanced.to_vec(), brick.get_vertices());
+    }
+
+    let mut particles = Particles::new(16);
+    particles.spawn_debris(&bricks[0], [0.5, 0.0, 0.1]);
+    particles.update(0.1);
+    assert_eq!(particles.instances().len() * 6, particles.vertices().len());
+}
+
+/// Keeps hold of every draw list it's given
+struct RecordingRenderer(Rc<RefCell<Vec<DrawList>>>);
+impl Renderer for RecordingRenderer {
+    fn set_atlas(&mut self, _atlas: &Atlas) {}
+    fn render(&mut self, draw_list: &DrawList) {
+        self.0.borrow_mut().push(draw_list.clone());
+    }
+    fn present(&mut self) {}
+    fn size(&self) -> (f32, f32) {
+        (800.0, 600.0)
+    }
+    fn screenshot(&self) -> Option<Image> {
+        None
+    }
+}
+
+#[test]
+fn draw_lists_need_no_window() {
+    let mut settings = Settings::default();
+    settings.video.ball_trail = false;
+    let logic_state = LogicState::new(&settings);
+
+    let draw_lists = Rc::new(RefCell::new(Vec::new()));
+    let mut video_state =
+        VideoState::with_renderer(Box::new(RecordingRenderer(draw_lists.clone())), &settings);
+    video_state.update(&logic_state, Duration::ZERO, 0, false);
+    video_state.update(&logic_state, Duration::ZERO, 1, false);
+
+    let draw_lists = draw_lists.borrow();
+    assert_eq!(draw_lists.len(), 2);
+    assert_eq!(draw_lists[0], draw_lists[1]);
+    assert_eq!(draw_lists[0].camera, DrawList::IDENTITY);
+
+    // the playfield and bricks are marked for caching, the ball and paddle aren't
+    let commands = &draw_lists[0].commands;
+    assert!(matches!(
+        commands[0],
+        DrawCommand::Triangles {
+            cache: Some(CacheKey {
+                name: "playfield",
+                ..
+            }),
+            ..
+        }
+    ));
+    let bricks = settings.logic.brick_rows * settings.logic.brick_columns;
+    assert!(matches!(
+        &commands[1],
+        DrawCommand::Quads { instances, cache: Some(CacheKey { name: "bricks", version }) }
+            if instances.len() == bricks && *version == bricks
+    ));
+    assert!(matches!(
+        &commands[2],
+        DrawCommand::Triangles { vertices, cache: None } if vertices.len() == 6 + 3
+    ));
+    assert_eq!(commands.len(), 3);
+}
+
+#[test]
+fn text_is_drawn_a_pixel_at_a_time() {
+    let text = Text {
+        text: "1".to_string(),
+        x: 0.0,
+        y: 0.0,
+        size: 0.5,
+        color: [1.0; 3],
+    };
+    let instances = text.instances();
+    assert_eq!(instances.len(), 8);
+    // the 1's foot spans the bottom row
+    assert!(instances.iter().any(|instance| {
+        (instance.offset[0] - 0.05).abs() < 1e-6 && (instance.offset[1] + 0.45).abs() < 1e-6
+    }));
+    assert_eq!(crate::view::video::font::text_width("11", 0.5), 0.7);
+}
+
+/// Compare `image` against `tests/golden/<name>.png`, or overwrite the golden image instead if
+/// `UPDATE_GOLDEN` is set
+fn assert_matches_golden(image: &Image, name: &str) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(name)
+        .with_extension("png");
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        encode_png(image, File::create(&path).unwrap()).unwrap();
+        return;
+    }
+
+    let golden = decode_png(File::open(&path).unwrap()).unwrap();
+    assert_eq!((image.width, image.height), (golden.width, golden.height));
+
+    // a little leeway for rounding
+    let differences = image
+        .pixels
+        .iter()
+        .zip(golden.pixels.iter())
+        .filter(|(a, b)| a.abs_diff(**b) > 2)
+        .count();
+    if differences > 0 {
+        let actual = std::env::temp_dir().join(format!("{}.actual.png", name));
+        encode_png(image, File::create(&actual).unwrap()).unwrap();
+        panic!(
+            "{} channels differ from {}, rendered frame written to {}",
+            differences,
+            path.display(),
+            actual.display()
+        );
+    }
+}
+
+fn render(settings: &Settings, width: u32, height: u32) -> Image {
+    let logic_state = LogicState::new(settings);
+    let mut scene = Scene::new(settings);
+    scene.update(&logic_state, std::time::Instant::now());
+
+    let mut renderer = SoftwareRenderer::new(width, height).with_post(settings.video.post.clone());
+    renderer.render(&scene.draw_list(&logic_state));
+    renderer.image().clone()
+}
+
+#[test]
+fn new_game_matches_golden() {
+    let settings = Settings::default();
+    assert_matches_golden(&render(&settings, 160, 120), "new_game");
+
+    // batched and unbatched draw lists look the same
+    let mut unbatched = settings.clone();
+    unbatched.video.persistent_buffers = false;
+    assert_matches_golden(&render(&unbatched, 160, 120), "new_game");
+}
+
+#[test]
+fn post_processing_matches_golden() {
+    let mut settings = Settings::default();
+    settings.video.post.enabled = true;
+    assert_matches_golden(&render(&settings, 160, 120), "post_processed");
+
+    // on, but with nothing to do, is the same as off
+    settings.video.post.chain.clear();
+    assert_matches_golden(&render(&settings, 160, 120), "new_game");
+}
+
+#[test]
+fn post_effects_do_what_they_say() {
+    let mut settings = PostSettings {
+        enabled: true,
+        chain: Vec::new(),
+        ..PostSettings::default()
+    };
+    // grey, with one white pixel in the middle
+    let mut image = Image {
+        width: 13,
+        height: 13,
+        pixels: [128, 128, 128, 255].repeat(169),
+    };
+    image.pixels[84 * 4..84 * 4 + 3].copy_from_slice(&[255; 3]);
+    let at = |image: &Image, x: usize, y: usize| image.pixels[(y * 13 + x) * 4];
+
+    settings.chain = vec![PostEffect::Bloom];
+    let bloomed = post::apply(&image, &settings);
+    assert!(at(&bloomed, 6, 4) > 128);
+    assert_eq!(at(&bloomed, 0, 0), 128);
+
+    settings.chain = vec![PostEffect::Vignette];
+    let vignetted = post::apply(&image, &settings);
+    assert!(at(&vignetted, 0, 0) < at(&vignetted, 5, 6));
+    assert_eq!(at(&vignetted, 6, 6), 255);
+
+    settings.chain = vec![PostEffect::Crt];
+    let crt = post::apply(&image, &settings);
+    assert_ne!(at(&crt, 5, 5), at(&crt, 5, 6));
+    assert_eq!(post::curved([0.0, 0.0], settings.curvature), None);
+    assert_eq!(
+        post::curved([0.5, 0.5], settings.curvature),
+        Some([0.5, 0.5])
+    );
+
+    settings.enabled = false;
+    assert_eq!(post::apply(&image, &settings), image);
+}
+
+#[test]
+fn themes_match_golden() {
+    let mut settings = Settings::default();
+    settings.video.theme = "deuteranopia".to_owned();
+    settings.video.theme_dir = Some(PathBuf::from("no-themes-here"));
+    assert_matches_golden(&render(&settings, 160, 120), "deuteranopia");
+}
+
+#[test]
+fn themes_color_every_kind_of_object() {
+    let settings = Settings::default();
+    let themes = Theme::builtin(&settings.logic);
+    assert_eq!(themes[0], Theme::classic(&settings.logic));
+    for (index, theme) in themes.iter().enumerate() {
+        assert!(!theme.bricks.is_empty());
+        assert!(themes[..index].iter().all(|other| other.name != theme.name));
+    }
+
+    let theme = &themes[1];
+    let logic_state = LogicState::new(&settings);
+    let rows = settings.logic.brick_rows;
+    for game_obj in logic_state.game_objs() {
+        let color = theme.color(game_obj, rows);
+        match game_obj.kind {
+            GameObjectKind::Paddle => assert_eq!(color, theme.paddle),
+            GameObjectKind::Ball => assert_eq!(color, theme.ball),
+            GameObjectKind::Playfield => assert_eq!(color, theme.playfield),
+            GameObjectKind::Brick(_) => assert!(theme.bricks.contains(&color)),
+        }
+    }
+    // rows are counted from the top
+    let top = GameObject::brick(0.0, (rows as f32 - 0.5) / rows as f32, 0.1, 0.1, [0.0; 3]);
+    let bottom = GameObject::brick(0.0, 0.5 / rows as f32, 0.1, 0.1, [0.0; 3]);
+    assert_eq!(theme.color(&top, rows), theme.brick(0));
+    assert_eq!(theme.color(&bottom, rows), theme.brick(rows - 1));
+
+    // switching themes redraws what's cached
+    let mut scene = Scene::new(&settings);
+    let before = scene.draw_list(&logic_state);
+    scene.next_theme();
+    assert_eq!(scene.theme().name, theme.name);
+    let after = scene.draw_list(&logic_state);
+    for (before, after) in before.commands.iter().zip(after.commands.iter()).take(2) {
+        let (DrawCommand::Triangles {
+            cache: Some(before),
+            ..
+        }
+        | DrawCommand::Quads {
+            cache: Some(before),
+            ..
+        }) = before
+        else {
+            panic!("expected cached geometry");
+        };
+        let (DrawCommand::Triangles {
+            cache: Some(after), ..
+        }
+        | DrawCommand::Quads {
+            cache: Some(after), ..
+        }) = after
+        else {
+            panic!("expected cached geometry");
+        };
+        assert_ne!(before.version, after.version);
+    }
+
+    // and draws it in the new colors
+    let DrawCommand::Triangles { vertices, .. } = &after.commands[0] else {
+        panic!("expected the playfield first");
+    };
+    assert!(vertices
+        .iter()
+        .all(|vertex| vertex.color == theme.playfield));
+    let DrawCommand::Quads { instances, .. } = &after.commands[1] else {
+        panic!("expected the bricks next");
+    };
+    assert!(instances
+        .iter()
+        .all(|instance| theme.bricks.contains(&instance.color)));
+}
+
+#[test]
+fn theme_files_are_checked() {
+    let dir = std::env::temp_dir().join("breakout-theme-test");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let theme = "
+        name = 'dusk'
+        playfield = [0.1, 0.1, 0.2]
+        paddle = [1.0, 0.6, 0.2]
+        ball = [1.0, 1.0, 1.0]
+        bricks = [[0.9, 0.3, 0.3], [0.3, 0.6, 0.9]]
+    ";
+    std::fs::write(dir.join("dusk.toml"), theme).unwrap();
+    std::fs::write(
+        dir.join("bad.toml"),
+        theme.replace("0.9, 0.3, 0.3", "2, 0, 0"),
+    )
+    .unwrap();
+    std::fs::write(dir.join("empty.toml"), "name = 'empty'").unwrap();
+
+    let dusk = Theme::load(&dir.join("dusk.toml")).unwrap();
+    assert_eq!(dusk.brick(3), [0.3, 0.6, 0.9]);
+    assert_eq!(dusk.sparks, SPARK_COLOR);
+    assert!(matches!(
+        Theme::load(&dir.join("bad.toml")),
+        Err(ThemeError::Invalid(..))
+    ));
+    assert!(matches!(
+        Theme::load(&dir.join("empty.toml")),
+        Err(ThemeError::Parse(..))
+    ));
+
+    let logic = Settings::default().logic;
+    let themes = Theme::all(&logic, Some(&dir));
+    assert_eq!(themes.len(), Theme::builtin(&logic).len() + 1);
+    assert_eq!(themes.last(), Some(&dusk));
+}
+
+#[test]
+fn tall_windows_match_golden() {
+    let mut settings = Settings::default();
+    settings.logic.brick_rows = 3;
+    settings.logic.brick_columns = 5;
+    settings.logic.brick_width = 0.3;
+    settings.logic.brick_height = 0.2;
+    settings.logic.brick_color = [0.2, 0.4, 1.0];
+    settings.logic.paddle_width = 0.5;
+    settings.logic.paddle_height = 0.1;
+    assert_matches_golden(&render(&settings, 90, 160), "tall_window");
+}
+
+#[test]
+fn overlays_match_golden() {
+    let mut draw_list = DrawList::new(DrawList::IDENTITY);
+    draw_list.push(DrawCommand::Triangles {
+        vertices: quad(1.5, 1.5, [0.0, 0.5, 0.0]).to_vec(),
+        cache: None,
+    });
+    draw_list.push(DrawCommand::Triangles {
+        vertices: pause_overlay(),
+        cache: None,
+    });
+    draw_list.push(DrawCommand::Blended(
+        quad(1.0, 0.5, [0.0; 3])
+            .map(|vertex| BlendedVertex {
+                position: vertex.position,
+                color: [1.0, 0.0, 0.0, 0.5],
+            })
+            .to_vec(),
+    ));
+    draw_list.push(DrawCommand::Text(Text {
+        text: "Paused 1:23".to_string(),
+        x: -0.9,
+        y: 0.9,
+        size: 0.15,
+        color: [1.0, 1.0, 0.0],
+    }));
+
+    let mut renderer = SoftwareRenderer::new(128, 128);
+    renderer.render(&draw_list);
+    assert_matches_golden(renderer.image(), "overlays");
+}
+
+#[test]
+fn terminal_cells_pair_up_rows() {
+    // 2 wide, 4 tall: red, green, blue and white rows
+    let rows = [[255, 0, 0], [0, 255, 0], [0, 0, 255], [255, 255, 255]];
+    let image = Image {
+        width: 2,
+        height: 4,
+        pixels: rows
+            .iter()
+            .flat_map(|&[r, g, b]| [r, g, b, 255, r, g, b, 255])
+            .collect(),
+    };
+
+    assert_eq!(
+        cells(&image),
+        vec![
+            (rows[0], rows[1]),
+            (rows[0], rows[1]),
+            (rows[2], rows[3]),
+            (rows[2], rows[3]),
+        ]
+    );
+}
+
+#[test]
+fn screenshots_are_timestamped() {
+    let time =
+        std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_millis(1_709_993_702_250);
+    assert_eq!(
+        screenshot::file_name(time),
+        "breakout-2024-03-09-141502-250.png"
+    );
+    assert_eq!(
+        screenshot::file_name(std::time::SystemTime::UNIX_EPOCH),
+        "breakout-1970-01-01-000000-000.png"
+    );
+
+    let image = render(&Settings::default(), 16, 12);
+    let dir = std::env::temp_dir().join("breakout-screenshot-test");
+    let path = screenshot::save(&image, &dir).unwrap();
+    assert_eq!(decode_png(File::open(&path).unwrap()).unwrap(), image);
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn screenshot_results_wait_to_be_reported() {
+    let mut settings = Settings::default();
+    settings.video.screenshot_at = Some(1);
+    let logic_state = LogicState::new(&settings);
+    let draw_lists = Rc::new(RefCell::new(Vec::new()));
+    let mut video_state =
+        VideoState::with_renderer(Box::new(RecordingRenderer(draw_lists)), &settings);
+
+    video_state.update(&logic_state, Duration::ZERO, 0, false);
+    assert!(video_state.take_screenshots().is_empty());
+
+    // nothing to read back from, which is only a warning
+    video_state.update(&logic_state, Duration::ZERO, 1, false);
+    video_state.update(&logic_state, Duration::ZERO, 2, true);
+    let screenshots = video_state.take_screenshots();
+    assert_eq!(screenshots.len(), 2);
+    assert!(screenshots
+        .iter()
+        .all(|saved| matches!(saved, Err(screenshot::ScreenshotError::Unsupported))));
+    assert!(video_state.take_screenshots().is_empty());
+}
+
+#[test]
+fn debug_overlay_outlines_everything() {
+    let settings = Settings::default();
+    let mut logic_state = LogicState::new(&settings);
+    let control_state = crate::control::ControlState::new(&settings);
+    let start = Instant::now();
+    logic_state.update(&control_state, start, Duration::ZERO);
+    logic_state.update(&control_state, start, Duration::ZERO);
+
+    let mut overlay = DebugOverlay::new();
+    overlay.record(start, 1);
+    overlay.record(start + Duration::from_millis(20), logic_state.ticks());
+    assert_eq!(overlay.frame_time(), Duration::from_millis(20));
+    assert!((overlay.fps() - 50.0).abs() < 1e-3);
+
+    let mut draw_list = DrawList::new(DrawList::IDENTITY);
+    overlay.draw(&mut draw_list, &logic_state, &FrameStats::new(false));
+
+    // four edges for each object, then the too late line and the velocity vector
+    let objects = logic_state.game_objs().count();
+    assert!(matches!(
+        &draw_list.commands[0],
+        DrawCommand::Triangles { vertices, .. } if vertices.len() == (objects * 4 + 2) * 6
+    ));
+    assert!(matches!(
+        &draw_list.commands[0],
+        DrawCommand::Triangles { vertices, .. } if vertices[objects * 24..objects * 24 + 6]
+            .iter()
+            .all(|vertex| vertex.color == TOO_LATE_COLOR
+                && (vertex.position[1] - logic_state.too_late_y()).abs() <= LINE_WIDTH)
+    ));
+    // one bar for the one frame so far, and the budget line
+    assert!(matches!(
+        &draw_list.commands[2],
+        DrawCommand::Quads { instances, .. } if instances.len() == 2
+    ));
+    assert!(draw_list.commands.iter().any(|command| matches!(
+        command,
+        DrawCommand::Text(text) if text.text == "ticks 2  +1 this frame"
+    )));
+}
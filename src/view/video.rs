@@ -1,18 +1,75 @@
-use glium::backend::glutin::{Display, SimpleWindowBuilder};
-use glium::{
-    implement_vertex, index::IndicesSource, uniform, DrawParameters, Frame, Program, Surface,
-    VertexBuffer,
-};
-use glutin::surface::WindowSurface;
-use std::error::Error;
+use glium::implement_vertex;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
 use winit::event_loop::EventLoop;
-use winit::window::Window;
 
 use crate::logic::LogicState;
+use crate::settings::Settings;
+use crate::stats::{FrameStats, FrameTiming};
+
+pub mod particles;
+
+pub mod trail;
+
+pub mod camera;
+
+pub mod atlas;
+use atlas::{Atlas, Image, Sprite};
+
+pub mod batch;
+
+pub mod font;
+
+pub mod draw_list;
+use draw_list::DrawList;
+
+pub mod scene;
+use scene::Scene;
+
+pub mod opengl;
+use opengl::GliumRenderer;
+
+pub mod software;
+
+pub mod terminal;
+use terminal::TerminalRenderer;
+
+pub mod screenshot;
+use screenshot::ScreenshotError;
+
+pub mod post;
+
+pub mod theme;
+
+pub mod debug;
+use debug::DebugOverlay;
+
+pub const PAUSE_OVERLAY_COLOR: [f32; 3] = [0.3, 0.3, 0.3];
+pub const PAUSE_BAR_WIDTH: f32 = 0.1;
+pub const PAUSE_BAR_HEIGHT: f32 = 0.4;
 
 /// Things that can be drawn to the screen
 pub trait Drawable {
     fn get_vertices(&self) -> Vec<Vertex>;
+
+    /// Sprite to draw from `atlas` instead of flat colors, or `None` if there isn't one
+    fn get_textured_vertices(&self, _atlas: &Atlas) -> Option<Vec<TexturedVertex>> {
+        None
+    }
+}
+
+/// Something that can draw a [`DrawList`], be it to a window or somewhere else entirely
+pub trait Renderer {
+    /// Called once up front if there's a sprite atlas, before anything is rendered
+    fn set_atlas(&mut self, atlas: &Atlas);
+    /// Draw a frame, without showing it yet
+    fn render(&mut self, draw_list: &DrawList);
+    /// Show the last frame rendered
+    fn present(&mut self);
+    /// Size of whatever's being drawn to, in pixels
+    fn size(&self) -> (f32, f32);
+    /// Read back the last frame presented, if there's any way to
+    fn screenshot(&self) -> Option<Image>;
 }
 
 /// Flat-Shaded Vertex
@@ -23,77 +80,162 @@ pub struct Vertex {
 }
 implement_vertex!(Vertex, position, color);
 
+/// Flat-Shaded Vertex with an alpha channel, drawn on top of everything else
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BlendedVertex {
+    pub position: [f32; 3],
+    pub color: [f32; 4],
+}
+implement_vertex!(BlendedVertex, position, color);
+
+/// Vertex textured from the sprite atlas
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TexturedVertex {
+    pub position: [f32; 3],
+    pub tex_coords: [f32; 2],
+}
+implement_vertex!(TexturedVertex, position, tex_coords);
+
 pub struct VideoState {
-    window_width: f32,
-    window_height: f32,
+    scene: Scene,
+    renderer: Box<dyn Renderer>,
 
-    flat_shader: Program,
-    window: Window,
-    display: Display<WindowSurface>,
+    screenshot_dir: PathBuf,
+    /// Frame to take a screenshot of, from the command line
+    screenshot_at: Option<u64>,
+    /// How taking screenshots went, kept until it's safe to print, e.g. the terminal's back
+    screenshots: Vec<Result<PathBuf, ScreenshotError>>,
+
+    debug_overlay: DebugOverlay,
+    show_debug_overlay: bool,
+
+    stats: FrameStats,
 }
 impl VideoState {
-    pub fn new(event_loop: &EventLoop<()>) -> VideoState {
-        // set up opengl and winit
-        let (window, display) = SimpleWindowBuilder::new().build(event_loop);
-
-        let flat_shader = glium::Program::from_source(
-            &display,
-            include_str!("video/vert.glsl"),
-            include_str!("video/frag.glsl"),
-            None,
-        )
-        .expect("unable to compile shaders, exiting");
-
-        let window_size = window.inner_size();
+    pub fn new(event_loop: &EventLoop<()>, settings: &Settings) -> VideoState {
+        Self::with_renderer(Box::new(GliumRenderer::new(event_loop, settings)), settings)
+    }
+
+    /// Draw into the terminal rather than a window
+    pub fn terminal(settings: &Settings) -> std::io::Result<VideoState> {
+        // the scene warns about bad sprites and themes, which has to happen before the terminal's
+        // taken over
+        let scene = Scene::new(settings);
+        Ok(Self::with_scene(
+            scene,
+            Box::new(TerminalRenderer::new()?),
+            settings,
+        ))
+    }
+
+    /// Draw with something other than OpenGL
+    pub fn with_renderer(renderer: Box<dyn Renderer>, settings: &Settings) -> VideoState {
+        Self::with_scene(Scene::new(settings), renderer, settings)
+    }
+
+    fn with_scene(
+        scene: Scene,
+        mut renderer: Box<dyn Renderer>,
+        settings: &Settings,
+    ) -> VideoState {
+        if let Some(atlas) = scene.atlas() {
+            renderer.set_atlas(atlas);
+        }
+
         VideoState {
-            window_width: (window_size.width as f32),
-            window_height: (window_size.height as f32),
+            scene,
+            renderer,
+
+            screenshot_dir: settings
+                .video
+                .screenshot_dir
+                .clone()
+                .unwrap_or_else(|| PathBuf::from(".")),
+            screenshot_at: settings.video.screenshot_at,
+            screenshots: Vec::new(),
 
-            flat_shader,
-            window,
-            display,
+            debug_overlay: DebugOverlay::new(),
+            show_debug_overlay: false,
+
+            stats: FrameStats::new(settings.video.frame_stats.is_some()),
         }
     }
 
-    pub fn update(&mut self, logic_state: &LogicState) {
-        // video
-        let mut frame = self.display.draw();
+    /// Draw frame number `frame`, and save it too if `screenshot` or it's the one asked for on
+    /// the command line. `logic_time` is how long the game took to update for it.
+    pub fn update(
+        &mut self,
+        logic_state: &LogicState,
+        logic_time: Duration,
+        frame: u64,
+        screenshot: bool,
+    ) {
+        let now = Instant::now();
+        self.scene.update(logic_state, now);
+        self.debug_overlay.record(now, logic_state.ticks());
+
+        let mut draw_list = self.scene.draw_list(logic_state);
+        if self.show_debug_overlay {
+            self.debug_overlay
+                .draw(&mut draw_list, logic_state, &self.stats);
+        }
+        self.renderer.render(&draw_list);
+        let render = now.elapsed();
 
-        frame.clear(None, Some((0.0, 0.0, 0.0, 1.0)), false, None, None);
+        let presenting = Instant::now();
+        self.renderer.present();
+        self.stats.record(FrameTiming {
+            logic: logic_time,
+            render,
+            present: presenting.elapsed(),
+        });
 
-        let mut vertices = Vec::new();
-        for game_obj in logic_state.game_objs() {
-            vertices.extend(game_obj.get_vertices());
+        if screenshot || self.screenshot_at == Some(frame) {
+            self.save_screenshot();
         }
+    }
+
+    fn save_screenshot(&mut self) {
+        // not worth stopping the game over, so it's only reported
+        let saved = match self.renderer.screenshot() {
+            Some(image) => screenshot::save(&image, &self.screenshot_dir),
+            None => Err(ScreenshotError::Unsupported),
+        };
+        self.screenshots.push(saved);
+    }
 
-        self.draw_flat_vertices(&vertices, &mut frame)
-            .expect("unable to complete draw call, exiting");
+    /// How the screenshots taken since the last call went
+    pub fn take_screenshots(&mut self) -> Vec<Result<PathBuf, ScreenshotError>> {
+        std::mem::take(&mut self.screenshots)
+    }
+
+    /// Switch to the next color theme
+    pub fn next_theme(&mut self) {
+        self.scene.next_theme();
+    }
 
-        frame.finish().expect("unable to finish frame, exiting");
+    /// Draw collision boxes and frame stats over the game, or stop
+    pub fn show_debug_overlay(&mut self, shown: bool) {
+        self.show_debug_overlay = shown;
     }
 
     pub fn window_size(&self) -> (f32, f32) {
-        (self.window_width, self.window_height)
+        self.renderer.size()
     }
 
-    fn draw_flat_vertices(
-        &self,
-        vertices: &Vec<Vertex>,
-        frame: &mut Frame,
-    ) -> Result<(), Box<dyn Error>> {
-        let uniforms = uniform! {
-            window_aspect: self.window.inner_size().width as f32 / self.window.inner_size().height as f32,
-        };
-        frame.draw(
-            &VertexBuffer::new(&self.display, vertices).expect("unable to construct vbo, exiting"),
-            IndicesSource::NoIndices {
-                primitives: glium::index::PrimitiveType::TrianglesList,
-            },
-            &self.flat_shader,
-            &uniforms,
-            &DrawParameters::default(),
-        )?;
-        Ok(())
+    /// How long frames take to put together, on average, for comparing ways of drawing them
+    pub fn average_draw_time(&self) -> Duration {
+        self.stats.average_render()
+    }
+
+    /// How long frames so far took to update, draw and show
+    pub fn frame_stats(&self) -> &FrameStats {
+        &self.stats
+    }
+
+    /// Whether geometry is kept on the GPU between frames
+    pub fn persistent_buffers(&self) -> bool {
+        self.scene.batching()
     }
 }
 
@@ -127,6 +269,37 @@ pub const fn quad(width: f32, height: f32, color: [f32; 3]) -> [Vertex; 6] {
     ]
 }
 
+/// Return a quad of specified width and height, covered by `sprite`
+pub fn textured_quad(width: f32, height: f32, sprite: Sprite) -> [TexturedVertex; 6] {
+    let (x, y) = (width / 2.0, height / 2.0);
+    [
+        ([x, y], [sprite.right, sprite.top]),
+        ([-x, y], [sprite.left, sprite.top]),
+        ([-x, -y], [sprite.left, sprite.bottom]),
+        ([x, y], [sprite.right, sprite.top]),
+        ([-x, -y], [sprite.left, sprite.bottom]),
+        ([x, -y], [sprite.right, sprite.bottom]),
+    ]
+    .map(|([x, y], tex_coords)| TexturedVertex {
+        position: [x, y, 0.0],
+        tex_coords,
+    })
+}
+
+/// Return the two bars of a pause symbol, centered on the playfield
+pub fn pause_overlay() -> Vec<Vertex> {
+    let mut vertices = Vec::new();
+    for x_offset in [-PAUSE_BAR_WIDTH, PAUSE_BAR_WIDTH] {
+        vertices.extend(
+            quad(PAUSE_BAR_WIDTH, PAUSE_BAR_HEIGHT, PAUSE_OVERLAY_COLOR).map(|mut vertex| {
+                vertex.position[0] += x_offset;
+                vertex
+            }),
+        );
+    }
+    vertices
+}
+
 /// Return an isosceles triangle pointing down
 pub const fn iso_tri_down(width: f32, height: f32, color: [f32; 3]) -> [Vertex; 3] {
     [
@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+/// 8-bit RGBA pixels, top row first
+#[derive(Clone, Debug, PartialEq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+/// Where a sprite is in the atlas, as texture coordinates with the origin at the bottom left
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Sprite {
+    pub left: f32,
+    pub bottom: f32,
+    pub right: f32,
+    pub top: f32,
+}
+
+/// Description of an atlas, read from a TOML file alongside its image:
+///
+/// ```toml
+/// image = "sprites.png"
+///
+/// [sprites]
+/// paddle = [0, 0, 64, 8] # x, y, width, height in pixels, from the top left
+/// ```
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SpriteSheet {
+    /// Relative to the description
+    pub image: PathBuf,
+    pub sprites: HashMap<String, [u32; 4]>,
+}
+
+#[derive(Debug)]
+pub enum AtlasError {
+    Io(PathBuf, io::Error),
+    Png(PathBuf, png::DecodingError),
+    Parse(PathBuf, toml::de::Error),
+    OutOfBounds(String),
+}
+impl fmt::Display for AtlasError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AtlasError::Io(path, e) => write!(f, "unable to read {}: {}", path.display(), e),
+            AtlasError::Png(path, e) => write!(f, "unable to decode {}: {}", path.display(), e),
+            AtlasError::Parse(path, e) => write!(f, "unable to parse {}: {}", path.display(), e),
+            AtlasError::OutOfBounds(name) => {
+                write!(f, "sprite '{}' doesn't fit inside the atlas image", name)
+            }
+        }
+    }
+}
+impl Error for AtlasError {}
+
+/// One image holding every sprite, so they can all be drawn from a single texture
+#[derive(Clone, Debug, PartialEq)]
+pub struct Atlas {
+    pub image: Image,
+    sprites: HashMap<String, Sprite>,
+}
+impl Atlas {
+    /// Load the atlas described by the TOML file at `path`
+    pub fn load(path: &Path) -> Result<Atlas, AtlasError> {
+        let text = fs::read_to_string(path).map_err(|e| AtlasError::Io(path.to_owned(), e))?;
+        let sheet: SpriteSheet =
+            toml::from_str(&text).map_err(|e| AtlasError::Parse(path.to_owned(), e))?;
+
+        let image_path = path
+            .parent()
+            .map_or_else(|| sheet.image.clone(), |dir| dir.join(&sheet.image));
+        let file = File::open(&image_path).map_err(|e| AtlasError::Io(image_path.to_owned(), e))?;
+        let image = decode_png(BufReader::new(file))
+            .map_err(|e| AtlasError::Png(image_path.to_owned(), e))?;
+
+        Atlas::new(image, &sheet)
+    }
+
+    pub fn new(image: Image, sheet: &SpriteSheet) -> Result<Atlas, AtlasError> {
+        let mut sprites = HashMap::new();
+        for (name, &[x, y, width, height]) in sheet.sprites.iter() {
+            if width == 0
+                || height == 0
+                || x.saturating_add(width) > image.width
+                || y.saturating_add(height) > image.height
+            {
+                return Err(AtlasError::OutOfBounds(name.clone()));
+            }
+
+            // rows are stored top first, but texture coordinates start at the bottom
+            let (image_width, image_height) = (image.width as f32, image.height as f32);
+            sprites.insert(
+                name.clone(),
+                Sprite {
+                    left: x as f32 / image_width,
+                    right: (x + width) as f32 / image_width,
+                    top: 1.0 - y as f32 / image_height,
+                    bottom: 1.0 - (y + height) as f32 / image_height,
+                },
+            );
+        }
+
+        Ok(Atlas { image, sprites })
+    }
+
+    pub fn sprite(&self, name: &str) -> Option<Sprite> {
+        self.sprites.get(name).copied()
+    }
+}
+
+/// Encode `image` as an 8-bit RGBA PNG
+pub fn encode_png(image: &Image, writer: impl Write) -> Result<(), png::EncodingError> {
+    let mut encoder = png::Encoder::new(writer, image.width, image.height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&image.pixels)?;
+    writer.finish()
+}
+
+/// Decode a PNG of any color type to 8-bit RGBA
+pub fn decode_png(reader: impl Read) -> Result<Image, png::DecodingError> {
+    let mut decoder = png::Decoder::new(reader);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info()?;
+
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer)?;
+    let bytes = &buffer[..info.buffer_size()];
+
+    let pixels = match info.color_type {
+        png::ColorType::Rgba => bytes.to_vec(),
+        png::ColorType::Rgb => bytes
+            .chunks_exact(3)
+            .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], u8::MAX])
+            .collect(),
+        png::ColorType::GrayscaleAlpha => bytes
+            .chunks_exact(2)
+            .flat_map(|ga| [ga[0], ga[0], ga[0], ga[1]])
+            .collect(),
+        // indexed images are expanded to rgb(a) by the decoder
+        png::ColorType::Grayscale | png::ColorType::Indexed => {
+            bytes.iter().flat_map(|&g| [g, g, g, u8::MAX]).collect()
+        }
+    };
+
+    Ok(Image {
+        width: info.width,
+        height: info.height,
+        pixels,
+    })
+}
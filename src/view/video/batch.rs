@@ -0,0 +1,25 @@
+use glium::implement_vertex;
+
+use crate::logic::game_objs::GameObject;
+
+/// Where and how big to draw one copy of the unit quad, and in what color
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Instance {
+    pub offset: [f32; 2],
+    pub size: [f32; 2],
+    pub color: [f32; 3],
+}
+implement_vertex!(Instance, offset, size, color);
+
+/// One instance per brick, in the color given with it
+pub fn brick_instances<'a>(
+    bricks: impl Iterator<Item = (&'a GameObject, [f32; 3])>,
+) -> Vec<Instance> {
+    bricks
+        .map(|(brick, color)| Instance {
+            offset: [brick.x, brick.y],
+            size: [brick.width, brick.height],
+            color,
+        })
+        .collect()
+}
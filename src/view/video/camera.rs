@@ -0,0 +1,74 @@
+use crate::logic::rng::Rng;
+
+/// Shake from breaking a brick
+pub const BRICK_TRAUMA: f32 = 0.3;
+/// Shake from losing a ball
+pub const BALL_LOST_TRAUMA: f32 = 0.8;
+/// Trauma lost per second
+pub const TRAUMA_DECAY: f32 = 2.0;
+/// Furthest the view moves at full trauma, in playfield units
+pub const MAX_SHAKE_OFFSET: f32 = 0.04;
+/// Furthest the view turns at full trauma, in degrees
+pub const MAX_SHAKE_ANGLE: f32 = 2.0;
+
+/// Transforms the playfield before it's drawn, so the whole view can be shaken
+#[derive(Clone, Debug)]
+pub struct Camera {
+    /// How shaken up we are, decaying back to 0 over time
+    trauma: f32,
+    /// Scales all shaking, 0 turns it off
+    intensity: f32,
+
+    x: f32,
+    y: f32,
+    angle: f32,
+
+    rng: Rng,
+}
+impl Camera {
+    pub fn new(intensity: f32) -> Camera {
+        Camera {
+            trauma: 0.0,
+            intensity,
+
+            x: 0.0,
+            y: 0.0,
+            angle: 0.0,
+
+            rng: Rng::new(0xca3e7a),
+        }
+    }
+
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).min(1.0);
+    }
+
+    pub fn update(&mut self, delta_t: f32) {
+        self.trauma = (self.trauma - TRAUMA_DECAY * delta_t).max(0.0);
+
+        // squared so small knocks stay subtle
+        let shake = self.intensity * self.trauma * self.trauma;
+        self.x = shake * MAX_SHAKE_OFFSET * self.random_signed();
+        self.y = shake * MAX_SHAKE_OFFSET * self.random_signed();
+        self.angle = shake * MAX_SHAKE_ANGLE.to_radians() * self.random_signed();
+    }
+
+    pub fn offset(&self) -> (f32, f32) {
+        (self.x, self.y)
+    }
+
+    /// Column-major 2D rotation and translation, for the `camera` uniform
+    pub fn matrix(&self) -> [[f32; 4]; 4] {
+        let (sin, cos) = self.angle.sin_cos();
+        [
+            [cos, sin, 0.0, 0.0],
+            [-sin, cos, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [self.x, self.y, 0.0, 1.0],
+        ]
+    }
+
+    fn random_signed(&mut self) -> f32 {
+        self.rng.next_f32() * 2.0 - 1.0
+    }
+}
@@ -0,0 +1,246 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::logic::game_objs::GameObject;
+use crate::logic::LogicState;
+use crate::stats::FrameStats;
+use crate::view::video::batch::Instance;
+use crate::view::video::draw_list::{DrawCommand, DrawList, Text};
+use crate::view::video::font;
+use crate::view::video::{BlendedVertex, Vertex};
+
+/// How many of the latest frames the graph shows, the same as the percentiles beside it cover
+pub const GRAPH_FRAMES: usize = crate::stats::RECENT_FRAMES;
+pub const GRAPH_WIDTH: f32 = 0.6;
+pub const GRAPH_HEIGHT: f32 = 0.3;
+/// Frame time at the top of the graph, anything slower is cut off
+pub const GRAPH_MAX_FRAME_TIME: Duration = Duration::from_millis(50);
+/// Frame times up to this are drawn green, up to twice it yellow, and red after that
+pub const FRAME_BUDGET: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+pub const AABB_COLOR: [f32; 3] = [0.0, 1.0, 0.0];
+pub const VELOCITY_COLOR: [f32; 3] = [1.0, 0.0, 1.0];
+pub const TOO_LATE_COLOR: [f32; 3] = [1.0, 0.0, 0.0];
+pub const TEXT_COLOR: [f32; 3] = [1.0, 1.0, 1.0];
+/// Darkens whatever's behind the graph and text so they can be read over anything
+pub const BACKDROP_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 0.6];
+pub const LINE_WIDTH: f32 = 0.005;
+/// How far ahead the velocity vector reaches, in seconds of the ball's movement
+pub const VELOCITY_SCALE: f32 = 0.25;
+pub const TEXT_SIZE: f32 = 0.04;
+/// Space around the edges of the playfield
+const MARGIN: f32 = 0.02;
+
+/// Collision boxes and the ball's velocity drawn over the game, with how fast frames and ticks
+/// are going in the corner
+#[derive(Clone, Debug, Default)]
+pub struct DebugOverlay {
+    /// Newest last
+    frame_times: VecDeque<Duration>,
+    last_frame: Option<Instant>,
+    ticks: u64,
+    /// Logic ticks run since the frame before
+    frame_ticks: u64,
+}
+impl DebugOverlay {
+    pub fn new() -> DebugOverlay {
+        DebugOverlay::default()
+    }
+
+    /// Note down a frame drawn at `now`, with the logic `ticks` in so far
+    pub fn record(&mut self, now: Instant, ticks: u64) {
+        if let Some(then) = self.last_frame {
+            if self.frame_times.len() == GRAPH_FRAMES {
+                self.frame_times.pop_front();
+            }
+            self.frame_times.push_back(now.duration_since(then));
+        }
+        self.last_frame = Some(now);
+
+        self.frame_ticks = ticks.saturating_sub(self.ticks);
+        self.ticks = ticks;
+    }
+
+    /// Average time between frames in the graph
+    pub fn frame_time(&self) -> Duration {
+        self.frame_times.iter().sum::<Duration>() / self.frame_times.len().max(1) as u32
+    }
+
+    pub fn fps(&self) -> f32 {
+        match self.frame_time().as_secs_f32() {
+            0.0 => 0.0,
+            frame_time => 1.0 / frame_time,
+        }
+    }
+
+    /// Add the overlay to the top of `draw_list`, with percentiles from the latest of `stats`
+    pub fn draw(&self, draw_list: &mut DrawList, logic_state: &LogicState, stats: &FrameStats) {
+        let mut vertices = Vec::new();
+        for game_obj in logic_state.game_objs() {
+            vertices.extend(outline(game_obj));
+        }
+
+        let too_late = logic_state.too_late_y();
+        vertices.extend(line((-1.0, too_late), (1.0, too_late), TOO_LATE_COLOR));
+
+        let ball = logic_state.ball();
+        vertices.extend(line(
+            (ball.x, ball.y),
+            (
+                ball.x + ball.x_v * VELOCITY_SCALE,
+                ball.y + ball.y_v * VELOCITY_SCALE,
+            ),
+            VELOCITY_COLOR,
+        ));
+        draw_list.push(DrawCommand::Triangles {
+            vertices,
+            cache: None,
+        });
+
+        let mut lines = vec![
+            format!(
+                "fps {:.1}  frame {:.1} ms",
+                self.fps(),
+                self.frame_time().as_secs_f64() * 1000.0
+            ),
+            format!("ticks {}  +{} this frame", self.ticks, self.frame_ticks),
+        ];
+        for (name, percentiles) in stats.recent_summary().phases() {
+            lines.push(format!("{:<8}{}", name, percentiles));
+        }
+        let line_height = TEXT_SIZE * 1.5;
+        let text_width = lines
+            .iter()
+            .map(|text| font::text_width(text, TEXT_SIZE))
+            .fold(0.0, f32::max);
+        let mut backdrops = backdrop(
+            -1.0 + MARGIN / 2.0,
+            1.0 - MARGIN / 2.0,
+            text_width + MARGIN,
+            line_height * lines.len() as f32 + MARGIN / 2.0,
+        );
+        backdrops.extend(backdrop(
+            -1.0 + MARGIN,
+            -1.0 + MARGIN + GRAPH_HEIGHT,
+            GRAPH_WIDTH,
+            GRAPH_HEIGHT,
+        ));
+        draw_list.push(DrawCommand::Blended(backdrops));
+
+        self.draw_graph(draw_list);
+
+        for (index, text) in lines.into_iter().enumerate() {
+            draw_list.push(DrawCommand::Text(Text {
+                text,
+                x: -1.0 + MARGIN,
+                y: 1.0 - MARGIN - index as f32 * line_height,
+                size: TEXT_SIZE,
+                color: TEXT_COLOR,
+            }));
+        }
+    }
+
+    /// A bar per frame along the bottom left, with a line across at the budget
+    fn draw_graph(&self, draw_list: &mut DrawList) {
+        let (left, bottom) = (-1.0 + MARGIN, -1.0 + MARGIN);
+        let bar_width = GRAPH_WIDTH / GRAPH_FRAMES as f32;
+        let scale = GRAPH_HEIGHT / GRAPH_MAX_FRAME_TIME.as_secs_f32();
+
+        let mut instances: Vec<Instance> = self
+            .frame_times
+            .iter()
+            .enumerate()
+            .map(|(index, &frame_time)| {
+                let height = (frame_time.as_secs_f32() * scale).min(GRAPH_HEIGHT);
+                Instance {
+                    offset: [
+                        left + (index as f32 + 0.5) * bar_width,
+                        bottom + height / 2.0,
+                    ],
+                    size: [bar_width, height],
+                    color: frame_time_color(frame_time),
+                }
+            })
+            .collect();
+        instances.push(Instance {
+            offset: [
+                left + GRAPH_WIDTH / 2.0,
+                bottom + FRAME_BUDGET.as_secs_f32() * scale,
+            ],
+            size: [GRAPH_WIDTH, LINE_WIDTH],
+            color: TEXT_COLOR,
+        });
+        draw_list.push(DrawCommand::Quads {
+            instances,
+            cache: None,
+        });
+    }
+}
+
+fn frame_time_color(frame_time: Duration) -> [f32; 3] {
+    if frame_time <= FRAME_BUDGET {
+        [0.0, 1.0, 0.0]
+    } else if frame_time <= FRAME_BUDGET * 2 {
+        [1.0, 1.0, 0.0]
+    } else {
+        [1.0, 0.0, 0.0]
+    }
+}
+
+/// A translucent dark rectangle with its top left corner at `left`, `top`
+fn backdrop(left: f32, top: f32, width: f32, height: f32) -> Vec<BlendedVertex> {
+    crate::view::video::quad(width, height, [0.0; 3])
+        .map(|vertex| BlendedVertex {
+            position: [
+                vertex.position[0] + left + width / 2.0,
+                vertex.position[1] + top - height / 2.0,
+                vertex.position[2],
+            ],
+            color: BACKDROP_COLOR,
+        })
+        .to_vec()
+}
+
+/// The edges of `game_obj`'s bounding box, as the game sees it for collisions
+pub fn outline(game_obj: &GameObject) -> Vec<Vertex> {
+    let (left, right) = (
+        game_obj.x - game_obj.width / 2.0,
+        game_obj.x + game_obj.width / 2.0,
+    );
+    let (bottom, top) = (
+        game_obj.y - game_obj.height / 2.0,
+        game_obj.y + game_obj.height / 2.0,
+    );
+    [
+        ((left, bottom), (right, bottom)),
+        ((right, bottom), (right, top)),
+        ((right, top), (left, top)),
+        ((left, top), (left, bottom)),
+    ]
+    .into_iter()
+    .flat_map(|(from, to)| line(from, to, AABB_COLOR))
+    .collect()
+}
+
+/// A line `LINE_WIDTH` thick, as two triangles
+pub fn line(from: (f32, f32), to: (f32, f32), color: [f32; 3]) -> [Vertex; 6] {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let length = (dx * dx + dy * dy).sqrt().max(f32::EPSILON);
+    // half the width, at right angles to the line
+    let (nx, ny) = (
+        -dy / length * LINE_WIDTH / 2.0,
+        dx / length * LINE_WIDTH / 2.0,
+    );
+    [
+        (from.0 + nx, from.1 + ny),
+        (from.0 - nx, from.1 - ny),
+        (to.0 - nx, to.1 - ny),
+        (from.0 + nx, from.1 + ny),
+        (to.0 - nx, to.1 - ny),
+        (to.0 + nx, to.1 + ny),
+    ]
+    .map(|(x, y)| Vertex {
+        position: [x, y, 0.0],
+        color,
+    })
+}
@@ -0,0 +1,85 @@
+use crate::view::video::batch::Instance;
+use crate::view::video::{font, BlendedVertex, TexturedVertex, Vertex};
+
+/// Identifies geometry that rarely changes, so renderers that can keep it around between frames
+/// only need to upload it again when `version` changes
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct CacheKey {
+    pub name: &'static str,
+    pub version: usize,
+}
+
+/// A line of text in the built in font
+#[derive(Clone, Debug, PartialEq)]
+pub struct Text {
+    pub text: String,
+    /// Top left corner
+    pub x: f32,
+    pub y: f32,
+    /// Height of a line
+    pub size: f32,
+    pub color: [f32; 3],
+}
+impl Text {
+    /// Every lit pixel of the text as a quad, for renderers without any text of their own
+    pub fn instances(&self) -> Vec<Instance> {
+        font::text_instances(&self.text, self.x, self.y, self.size, self.color)
+    }
+}
+
+/// One thing to draw, in playfield coordinates, i.e. before aspect ratio correction
+#[derive(Clone, Debug, PartialEq)]
+pub enum DrawCommand {
+    /// Flat-shaded triangles
+    Triangles {
+        vertices: Vec<Vertex>,
+        cache: Option<CacheKey>,
+    },
+    /// Copies of a 1x1 quad, moved, scaled and colored per instance
+    Quads {
+        instances: Vec<Instance>,
+        cache: Option<CacheKey>,
+    },
+    /// Triangles blended over whatever's already been drawn
+    Blended(Vec<BlendedVertex>),
+    /// Triangles textured from the sprite atlas, and blended
+    Sprites(Vec<TexturedVertex>),
+    Text(Text),
+}
+
+/// Everything that makes up a frame, in the order it's drawn, for any renderer to draw
+#[derive(Clone, Debug, PartialEq)]
+pub struct DrawList {
+    /// Column-major transform applied to everything, see [`crate::view::video::camera::Camera`]
+    pub camera: [[f32; 4]; 4],
+    pub commands: Vec<DrawCommand>,
+}
+impl DrawList {
+    pub const IDENTITY: [[f32; 4]; 4] = [
+        [1.0, 0.0, 0.0, 0.0],
+        [0.0, 1.0, 0.0, 0.0],
+        [0.0, 0.0, 1.0, 0.0],
+        [0.0, 0.0, 0.0, 1.0],
+    ];
+
+    pub fn new(camera: [[f32; 4]; 4]) -> DrawList {
+        DrawList {
+            camera,
+            commands: Vec::new(),
+        }
+    }
+
+    /// Add a command, leaving out anything with nothing to draw
+    pub fn push(&mut self, command: DrawCommand) {
+        let empty = match &command {
+            DrawCommand::Triangles { vertices, .. } => vertices.is_empty(),
+            DrawCommand::Quads { instances, .. } => instances.is_empty(),
+            DrawCommand::Blended(vertices) => vertices.is_empty(),
+            DrawCommand::Sprites(vertices) => vertices.is_empty(),
+            DrawCommand::Text(text) => text.text.is_empty(),
+        };
+        if !empty {
+            self.commands.push(command);
+        }
+    }
+}
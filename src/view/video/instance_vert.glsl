@@ -0,0 +1,23 @@
+#version 330 core
+uniform float window_aspect;
+uniform mat4 camera;
+
+in vec3 position;
+
+in vec2 offset;
+in vec2 size;
+in vec3 color;
+
+out vec3 f_color;
+
+void main() {
+    gl_Position = camera * vec4(position.xy * size + offset, position.z, 1.0);
+
+    if (window_aspect > 1.0) {
+        gl_Position.x /= window_aspect;
+    } else {
+        gl_Position.y *= window_aspect;
+    }
+
+    f_color = color;
+}
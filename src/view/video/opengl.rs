@@ -0,0 +1,573 @@
+use glium::backend::glutin::Display;
+use glium::framebuffer::SimpleFrameBuffer;
+use glium::texture::{MipmapsOption, RawImage2d, SrgbFormat, SrgbTexture2d};
+use glium::uniforms::{MagnifySamplerFilter, SamplerWrapFunction};
+use glium::{
+    index::IndicesSource, uniform, Blend, DrawParameters, Frame, Program, Surface, VertexBuffer,
+};
+use glutin::config::ConfigTemplateBuilder;
+use glutin::context::ContextAttributesBuilder;
+use glutin::display::GetGlDisplay;
+use glutin::prelude::*;
+use glutin::surface::{SurfaceAttributesBuilder, SwapInterval, WindowSurface};
+use glutin_winit::DisplayBuilder;
+use raw_window_handle::HasRawWindowHandle;
+use std::collections::HashMap;
+use std::error::Error;
+use std::num::NonZeroU32;
+use winit::dpi::PhysicalSize;
+use winit::event_loop::EventLoop;
+use winit::window::{Fullscreen, Window, WindowBuilder};
+
+use crate::settings::{PostEffect, PostSettings, Settings, WindowMode};
+use crate::view::video::atlas::{Atlas, Image, Sprite};
+use crate::view::video::batch::Instance;
+use crate::view::video::draw_list::{CacheKey, DrawCommand, DrawList};
+use crate::view::video::{
+    post, quad, textured_quad, BlendedVertex, Renderer, TexturedVertex, Vertex,
+};
+
+/// Draws to a window with OpenGL, keeping cacheable geometry on the GPU between frames
+pub struct GliumRenderer {
+    window_width: f32,
+    window_height: f32,
+
+    flat_shader: Program,
+    blend_shader: Program,
+    sprite_shader: Program,
+    instance_shader: Program,
+    window: Window,
+    display: Display<WindowSurface>,
+
+    atlas: Option<SrgbTexture2d>,
+
+    /// 1x1 and white, to be scaled and tinted per instance
+    unit_quad: VertexBuffer<Vertex>,
+    /// Uploaded again only when their version changes
+    cached_triangles: HashMap<&'static str, (usize, VertexBuffer<Vertex>)>,
+    cached_quads: HashMap<&'static str, (usize, VertexBuffer<Instance>)>,
+    /// Rewritten every frame, but only allocated when they need to grow, with how many
+    /// instances are in use
+    streaming: Vec<(VertexBuffer<Instance>, usize)>,
+
+    post: PostSettings,
+    post_shaders: HashMap<PostEffect, Program>,
+    /// Covers the whole window, for post-processing
+    screen_quad: VertexBuffer<TexturedVertex>,
+    /// Offscreen frames, the size of the window, that post-processing goes back and forth between
+    post_targets: Vec<SrgbTexture2d>,
+
+    /// Drawn, but not yet shown
+    frame: Option<Frame>,
+}
+impl GliumRenderer {
+    pub fn new(event_loop: &EventLoop<()>, settings: &Settings) -> GliumRenderer {
+        // set up opengl and winit
+        let window_builder =
+            WindowBuilder::new()
+                .with_title("breakout")
+                .with_inner_size(PhysicalSize::new(
+                    settings.video.window_width,
+                    settings.video.window_height,
+                ));
+        let (window, display) = build_display(event_loop, window_builder, settings.video.vsync);
+
+        if settings.video.window_mode == WindowMode::Fullscreen {
+            window.set_fullscreen(Some(Fullscreen::Borderless(None)));
+        }
+
+        let flat_shader = glium::Program::from_source(
+            &display,
+            include_str!("vert.glsl"),
+            include_str!("frag.glsl"),
+            None,
+        )
+        .expect("unable to compile shaders, exiting");
+        let blend_shader = glium::Program::from_source(
+            &display,
+            include_str!("blend_vert.glsl"),
+            include_str!("blend_frag.glsl"),
+            None,
+        )
+        .expect("unable to compile shaders, exiting");
+        let sprite_shader = glium::Program::from_source(
+            &display,
+            include_str!("sprite_vert.glsl"),
+            include_str!("sprite_frag.glsl"),
+            None,
+        )
+        .expect("unable to compile shaders, exiting");
+        let instance_shader = glium::Program::from_source(
+            &display,
+            include_str!("instance_vert.glsl"),
+            include_str!("frag.glsl"),
+            None,
+        )
+        .expect("unable to compile shaders, exiting");
+
+        let mut post_shaders = HashMap::new();
+        for (effect, source) in [
+            (PostEffect::Bloom, include_str!("post_bloom.glsl")),
+            (PostEffect::Crt, include_str!("post_crt.glsl")),
+            (PostEffect::Vignette, include_str!("post_vignette.glsl")),
+        ] {
+            let shader =
+                glium::Program::from_source(&display, include_str!("post_vert.glsl"), source, None)
+                    .expect("unable to compile shaders, exiting");
+            post_shaders.insert(effect, shader);
+        }
+
+        let unit_quad = VertexBuffer::new(&display, &quad(1.0, 1.0, [1.0; 3]))
+            .expect("unable to construct vbo, exiting");
+        let whole_texture = Sprite {
+            left: 0.0,
+            bottom: 0.0,
+            right: 1.0,
+            top: 1.0,
+        };
+        let screen_quad = VertexBuffer::new(&display, &textured_quad(2.0, 2.0, whole_texture))
+            .expect("unable to construct vbo, exiting");
+
+        let window_size = window.inner_size();
+        GliumRenderer {
+            window_width: (window_size.width as f32),
+            window_height: (window_size.height as f32),
+
+            flat_shader,
+            blend_shader,
+            sprite_shader,
+            instance_shader,
+            window,
+            display,
+
+            atlas: None,
+
+            unit_quad,
+            cached_triangles: HashMap::new(),
+            cached_quads: HashMap::new(),
+            streaming: Vec::new(),
+
+            post: settings.video.post.clone(),
+            post_shaders,
+            screen_quad,
+            post_targets: Vec::new(),
+
+            frame: None,
+        }
+    }
+
+    /// Make sure there's two offscreen frames the size of `frame`
+    fn resize_post_targets(&mut self, (width, height): (u32, u32)) {
+        if self
+            .post_targets
+            .first()
+            .is_some_and(|target| target.dimensions() == (width, height))
+        {
+            return;
+        }
+        self.post_targets = (0..2)
+            .map(|_| {
+                SrgbTexture2d::empty_with_format(
+                    &self.display,
+                    SrgbFormat::U8U8U8U8,
+                    MipmapsOption::NoMipmap,
+                    width,
+                    height,
+                )
+                .expect("unable to create offscreen frame, exiting")
+            })
+            .collect();
+    }
+
+    /// Draw `draw_list` offscreen, then run it through each post-processing effect in turn, the
+    /// last one drawing to `frame`
+    fn draw_post_processed(
+        &self,
+        draw_list: &DrawList,
+        frame: &mut Frame,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut target = SimpleFrameBuffer::new(&self.display, &self.post_targets[0])?;
+        target.clear_color(0.0, 0.0, 0.0, 1.0);
+        self.draw(draw_list, &mut target)?;
+
+        for (index, effect) in self.post.chain.iter().enumerate() {
+            let source = &self.post_targets[index % 2];
+            if index + 1 == self.post.chain.len() {
+                self.draw_post_effect(*effect, source, frame)?;
+            } else {
+                let mut target =
+                    SimpleFrameBuffer::new(&self.display, &self.post_targets[(index + 1) % 2])?;
+                self.draw_post_effect(*effect, source, &mut target)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn draw_post_effect(
+        &self,
+        effect: PostEffect,
+        source: &SrgbTexture2d,
+        target: &mut impl Surface,
+    ) -> Result<(), Box<dyn Error>> {
+        let (width, height) = source.dimensions();
+        let post = &self.post;
+        let uniforms = uniform! {
+            source: source.sampled().wrap_function(SamplerWrapFunction::Clamp),
+            resolution: [width as f32, height as f32],
+            threshold: post.bloom_threshold,
+            strength: post.bloom_strength,
+            spread: post::BLOOM_SPREAD as f32,
+            curvature: post.curvature,
+            scanlines: post.scanlines,
+            vignette: post.vignette,
+        };
+        target.draw(
+            &self.screen_quad,
+            IndicesSource::NoIndices {
+                primitives: glium::index::PrimitiveType::TrianglesList,
+            },
+            &self.post_shaders[&effect],
+            &uniforms,
+            &DrawParameters::default(),
+        )?;
+        Ok(())
+    }
+
+    /// Get everything that lives between frames up to date with `draw_list`
+    fn upload(&mut self, draw_list: &DrawList) {
+        let mut stream = 0;
+        for command in draw_list.commands.iter() {
+            match command {
+                DrawCommand::Triangles {
+                    vertices,
+                    cache: Some(key),
+                } if !is_cached(&self.cached_triangles, key) => {
+                    let buffer = VertexBuffer::new(&self.display, vertices)
+                        .expect("unable to construct vbo, exiting");
+                    self.cached_triangles
+                        .insert(key.name, (key.version, buffer));
+                }
+                DrawCommand::Quads {
+                    instances,
+                    cache: Some(key),
+                } if !is_cached(&self.cached_quads, key) => {
+                    let buffer = VertexBuffer::new(&self.display, instances)
+                        .expect("unable to construct vbo, exiting");
+                    self.cached_quads.insert(key.name, (key.version, buffer));
+                }
+                DrawCommand::Quads {
+                    instances,
+                    cache: None,
+                } => {
+                    self.stream(stream, instances);
+                    stream += 1;
+                }
+                DrawCommand::Text(text) => {
+                    self.stream(stream, &text.instances());
+                    stream += 1;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Write `instances` to the `index`th streaming buffer, growing it if need be
+    fn stream(&mut self, index: usize, instances: &[Instance]) {
+        if index >= self.streaming.len() || self.streaming[index].0.len() < instances.len() {
+            let buffer =
+                VertexBuffer::empty_dynamic(&self.display, instances.len().next_power_of_two())
+                    .expect("unable to construct vbo, exiting");
+            if index >= self.streaming.len() {
+                self.streaming.push((buffer, 0));
+            } else {
+                self.streaming[index].0 = buffer;
+            }
+        }
+
+        let (buffer, count) = &mut self.streaming[index];
+        if let Some(slice) = buffer.slice(0..instances.len()) {
+            slice.write(instances);
+        }
+        *count = instances.len();
+    }
+
+    fn draw(&self, draw_list: &DrawList, target: &mut impl Surface) -> Result<(), Box<dyn Error>> {
+        let camera = draw_list.camera;
+
+        let mut stream = 0;
+        for command in draw_list.commands.iter() {
+            match command {
+                DrawCommand::Triangles {
+                    cache: Some(key), ..
+                } => {
+                    let (_, buffer) = &self.cached_triangles[key.name];
+                    self.draw_flat_buffer(buffer, camera, target)?;
+                }
+                DrawCommand::Triangles {
+                    vertices,
+                    cache: None,
+                } => {
+                    let buffer = VertexBuffer::new(&self.display, vertices)
+                        .expect("unable to construct vbo, exiting");
+                    self.draw_flat_buffer(&buffer, camera, target)?;
+                }
+                DrawCommand::Quads {
+                    instances,
+                    cache: Some(key),
+                } => {
+                    let (_, buffer) = &self.cached_quads[key.name];
+                    self.draw_instances(buffer, instances.len(), camera, target)?;
+                }
+                DrawCommand::Quads { cache: None, .. } | DrawCommand::Text(_) => {
+                    let (buffer, count) = &self.streaming[stream];
+                    self.draw_instances(buffer, *count, camera, target)?;
+                    stream += 1;
+                }
+                DrawCommand::Blended(vertices) => {
+                    self.draw_blended_vertices(vertices, camera, target)?
+                }
+                DrawCommand::Sprites(vertices) => {
+                    self.draw_textured_vertices(vertices, camera, target)?
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn window_aspect(&self) -> f32 {
+        self.window.inner_size().width as f32 / self.window.inner_size().height as f32
+    }
+
+    fn draw_flat_buffer(
+        &self,
+        buffer: &VertexBuffer<Vertex>,
+        camera: [[f32; 4]; 4],
+        target: &mut impl Surface,
+    ) -> Result<(), Box<dyn Error>> {
+        let uniforms = uniform! {
+            window_aspect: self.window_aspect(),
+            camera: camera,
+        };
+        target.draw(
+            buffer,
+            IndicesSource::NoIndices {
+                primitives: glium::index::PrimitiveType::TrianglesList,
+            },
+            &self.flat_shader,
+            &uniforms,
+            &DrawParameters::default(),
+        )?;
+        Ok(())
+    }
+
+    /// Draw the first `count` of `instances`, each a copy of the unit quad
+    fn draw_instances(
+        &self,
+        instances: &VertexBuffer<Instance>,
+        count: usize,
+        camera: [[f32; 4]; 4],
+        target: &mut impl Surface,
+    ) -> Result<(), Box<dyn Error>> {
+        let Some(instances) = instances.slice(0..count) else {
+            return Ok(());
+        };
+        let uniforms = uniform! {
+            window_aspect: self.window_aspect(),
+            camera: camera,
+        };
+        target.draw(
+            (
+                &self.unit_quad,
+                instances
+                    .per_instance()
+                    .map_err(|_| "instanced drawing isn't supported")?,
+            ),
+            IndicesSource::NoIndices {
+                primitives: glium::index::PrimitiveType::TrianglesList,
+            },
+            &self.instance_shader,
+            &uniforms,
+            &DrawParameters::default(),
+        )?;
+        Ok(())
+    }
+
+    /// Same as `draw_flat_buffer`, but blended over whatever's already been drawn
+    fn draw_blended_vertices(
+        &self,
+        vertices: &[BlendedVertex],
+        camera: [[f32; 4]; 4],
+        target: &mut impl Surface,
+    ) -> Result<(), Box<dyn Error>> {
+        let uniforms = uniform! {
+            window_aspect: self.window_aspect(),
+            camera: camera,
+        };
+        target.draw(
+            &VertexBuffer::new(&self.display, vertices).expect("unable to construct vbo, exiting"),
+            IndicesSource::NoIndices {
+                primitives: glium::index::PrimitiveType::TrianglesList,
+            },
+            &self.blend_shader,
+            &uniforms,
+            &DrawParameters {
+                blend: Blend::alpha_blending(),
+                ..Default::default()
+            },
+        )?;
+        Ok(())
+    }
+
+    /// Draw sprites from the atlas, blended so their transparent bits show what's underneath
+    fn draw_textured_vertices(
+        &self,
+        vertices: &[TexturedVertex],
+        camera: [[f32; 4]; 4],
+        target: &mut impl Surface,
+    ) -> Result<(), Box<dyn Error>> {
+        let Some(texture) = &self.atlas else {
+            return Ok(());
+        };
+        let uniforms = uniform! {
+            window_aspect: self.window_aspect(),
+            camera: camera,
+            // keep pixel art crisp
+            atlas: texture.sampled().magnify_filter(MagnifySamplerFilter::Nearest),
+        };
+        target.draw(
+            &VertexBuffer::new(&self.display, vertices).expect("unable to construct vbo, exiting"),
+            IndicesSource::NoIndices {
+                primitives: glium::index::PrimitiveType::TrianglesList,
+            },
+            &self.sprite_shader,
+            &uniforms,
+            &DrawParameters {
+                blend: Blend::alpha_blending(),
+                ..Default::default()
+            },
+        )?;
+        Ok(())
+    }
+}
+impl Renderer for GliumRenderer {
+    fn set_atlas(&mut self, atlas: &Atlas) {
+        let image = RawImage2d::from_raw_rgba_reversed(
+            &atlas.image.pixels,
+            (atlas.image.width, atlas.image.height),
+        );
+        self.atlas = Some(
+            SrgbTexture2d::new(&self.display, image)
+                .expect("unable to upload sprite atlas, exiting"),
+        );
+    }
+
+    fn render(&mut self, draw_list: &DrawList) {
+        self.upload(draw_list);
+
+        let mut frame = self.display.draw();
+        if self.post.enabled && !self.post.chain.is_empty() {
+            self.resize_post_targets(frame.get_dimensions());
+            self.draw_post_processed(draw_list, &mut frame)
+                .expect("unable to complete draw call, exiting");
+        } else {
+            // passthrough, straight to the window
+            frame.clear(None, Some((0.0, 0.0, 0.0, 1.0)), false, None, None);
+            self.draw(draw_list, &mut frame)
+                .expect("unable to complete draw call, exiting");
+        }
+        self.frame = Some(frame);
+    }
+
+    fn present(&mut self) {
+        if let Some(frame) = self.frame.take() {
+            frame.finish().expect("unable to finish frame, exiting");
+        }
+    }
+
+    fn size(&self) -> (f32, f32) {
+        (self.window_width, self.window_height)
+    }
+
+    fn screenshot(&self) -> Option<Image> {
+        let raw: RawImage2d<u8> = self
+            .display
+            .read_front_buffer()
+            .map_err(|e| eprintln!("warning: unable to read back the frame: {:?}", e))
+            .ok()?;
+
+        // opengl's rows start at the bottom
+        let row_length = raw.width as usize * 4;
+        let mut pixels = Vec::with_capacity(raw.data.len());
+        for row in raw.data.chunks_exact(row_length).rev() {
+            pixels.extend(
+                row.chunks_exact(4)
+                    .flat_map(|p| [p[0], p[1], p[2], u8::MAX]),
+            );
+        }
+        Some(Image {
+            width: raw.width,
+            height: raw.height,
+            pixels,
+        })
+    }
+}
+
+/// A window and an OpenGL context to draw to it with, the same as glium's `SimpleWindowBuilder`
+/// makes, but with a say over vsync
+fn build_display(
+    event_loop: &EventLoop<()>,
+    window_builder: WindowBuilder,
+    vsync: bool,
+) -> (Window, Display<WindowSurface>) {
+    let (window, gl_config) = DisplayBuilder::new()
+        .with_window_builder(Some(window_builder))
+        .build(event_loop, ConfigTemplateBuilder::new(), |mut configs| {
+            configs.next().expect("no suitable opengl config, exiting")
+        })
+        .expect("unable to create window, exiting");
+    let window = window.expect("unable to create window, exiting");
+
+    let (width, height): (u32, u32) = window.inner_size().into();
+    let surface_attributes = SurfaceAttributesBuilder::<WindowSurface>::new().build(
+        window.raw_window_handle(),
+        NonZeroU32::new(width).unwrap_or(NonZeroU32::MIN),
+        NonZeroU32::new(height).unwrap_or(NonZeroU32::MIN),
+    );
+    let surface = unsafe {
+        gl_config
+            .display()
+            .create_window_surface(&gl_config, &surface_attributes)
+    }
+    .expect("unable to create window surface, exiting");
+
+    let context_attributes =
+        ContextAttributesBuilder::new().build(Some(window.raw_window_handle()));
+    let context = unsafe {
+        gl_config
+            .display()
+            .create_context(&gl_config, &context_attributes)
+    }
+    .expect("unable to create opengl context, exiting")
+    .make_current(&surface)
+    .expect("unable to make opengl context current, exiting");
+
+    let swap_interval = if vsync {
+        SwapInterval::Wait(NonZeroU32::MIN)
+    } else {
+        SwapInterval::DontWait
+    };
+    // some drivers insist on their own setting, not worth stopping over
+    if let Err(e) = surface.set_swap_interval(&context, swap_interval) {
+        eprintln!("warning: unable to set vsync: {}", e);
+    }
+
+    let display =
+        Display::from_context_surface(context, surface).expect("unable to set up glium, exiting");
+    (window, display)
+}
+
+fn is_cached<T>(cache: &HashMap<&'static str, (usize, T)>, key: &CacheKey) -> bool {
+    cache
+        .get(key.name)
+        .is_some_and(|(version, _)| *version == key.version)
+}
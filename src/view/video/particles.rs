@@ -0,0 +1,169 @@
+use crate::logic::game_objs::GameObject;
+use crate::logic::rng::Rng;
+use crate::view::video::{batch::Instance, quad, Vertex};
+
+pub const MAX_PARTICLES: usize = 512;
+
+/// Debris pieces per brick, across and down
+pub const DEBRIS_COLUMNS: usize = 4;
+pub const DEBRIS_ROWS: usize = 2;
+pub const DEBRIS_LIFETIME: f32 = 0.6;
+
+pub const SPARKS_PER_HIT: usize = 6;
+pub const SPARK_COLOR: [f32; 3] = [1.0, 0.8, 0.2];
+pub const SPARK_SIZE: f32 = 0.008;
+pub const SPARK_SPEED: f32 = 0.6;
+pub const SPARK_LIFETIME: f32 = 0.25;
+
+/// Pulls debris down, in playfield units per second squared
+pub const GRAVITY: f32 = -3.0;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Particle {
+    pub x: f32,
+    pub y: f32,
+    pub x_v: f32,
+    pub y_v: f32,
+    pub size: f32,
+    pub color: [f32; 3],
+    /// Seconds since spawning
+    pub age: f32,
+    pub lifetime: f32,
+    /// Whether gravity applies
+    pub falls: bool,
+}
+
+/// Every live particle, up to a fixed limit, after which new ones replace the oldest
+#[derive(Clone, Debug)]
+pub struct Particles {
+    particles: Vec<Particle>,
+    max_particles: usize,
+    rng: Rng,
+}
+impl Particles {
+    pub fn new(max_particles: usize) -> Particles {
+        Particles {
+            particles: Vec::with_capacity(max_particles),
+            max_particles,
+            rng: Rng::new(0xdeb715),
+        }
+    }
+
+    /// Break a brick into pieces flying away from its center
+    pub fn spawn_debris(&mut self, brick: &GameObject, color: [f32; 3]) {
+        let piece_width = brick.width / DEBRIS_COLUMNS as f32;
+        let piece_height = brick.height / DEBRIS_ROWS as f32;
+
+        for i in 0..DEBRIS_COLUMNS {
+            for j in 0..DEBRIS_ROWS {
+                let x_offset = (i as f32 + 0.5) * piece_width - brick.width / 2.0;
+                let y_offset = (j as f32 + 0.5) * piece_height - brick.height / 2.0;
+                let spread = 0.5 + self.rng.next_f32();
+
+                self.spawn(Particle {
+                    x: brick.x + x_offset,
+                    y: brick.y + y_offset,
+                    x_v: x_offset * 10.0 * spread,
+                    y_v: y_offset * 10.0 * spread + 0.3,
+                    size: piece_width.min(piece_height),
+                    color,
+                    age: 0.0,
+                    lifetime: DEBRIS_LIFETIME * spread,
+                    falls: true,
+                });
+            }
+        }
+    }
+
+    /// A little burst of sparks in every direction
+    pub fn spawn_sparks(&mut self, x: f32, y: f32, color: [f32; 3]) {
+        for _ in 0..SPARKS_PER_HIT {
+            let angle = self.rng.next_f32() * std::f32::consts::TAU;
+            let speed = SPARK_SPEED * (0.5 + self.rng.next_f32());
+
+            self.spawn(Particle {
+                x,
+                y,
+                x_v: angle.cos() * speed,
+                y_v: angle.sin() * speed,
+                size: SPARK_SIZE,
+                color,
+                age: 0.0,
+                lifetime: SPARK_LIFETIME,
+                falls: false,
+            });
+        }
+    }
+
+    pub fn spawn(&mut self, particle: Particle) {
+        if self.max_particles == 0 {
+            return;
+        }
+        if self.particles.len() < self.max_particles {
+            self.particles.push(particle);
+            return;
+        }
+
+        // full, so out with the oldest
+        let oldest = self
+            .particles
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| (a.age / a.lifetime).total_cmp(&(b.age / b.lifetime)))
+            .map(|(index, _)| index)
+            .expect("particle pool is full but empty? exiting");
+        self.particles[oldest] = particle;
+    }
+
+    /// Move everything along by `delta_t` seconds, and clear out whatever's expired
+    pub fn update(&mut self, delta_t: f32) {
+        for particle in self.particles.iter_mut() {
+            if particle.falls {
+                particle.y_v += GRAVITY * delta_t;
+            }
+            particle.x += particle.x_v * delta_t;
+            particle.y += particle.y_v * delta_t;
+            particle.age += delta_t;
+        }
+        self.particles
+            .retain(|particle| particle.age < particle.lifetime);
+    }
+
+    pub fn len(&self) -> usize {
+        self.particles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.particles.is_empty()
+    }
+
+    /// Every particle as an instance of the unit quad, shrinking as they age
+    pub fn instances(&self) -> Vec<Instance> {
+        self.particles
+            .iter()
+            .map(|particle| {
+                let size = particle.size * (1.0 - particle.age / particle.lifetime);
+                Instance {
+                    offset: [particle.x, particle.y],
+                    size: [size, size],
+                    color: particle.color,
+                }
+            })
+            .collect()
+    }
+
+    /// Every particle as one batch of quads, for when instancing isn't in use
+    pub fn vertices(&self) -> Vec<Vertex> {
+        let mut vertices = Vec::with_capacity(self.particles.len() * 6);
+        for instance in self.instances() {
+            vertices.extend(
+                quad(instance.size[0], instance.size[1], instance.color).map(|mut vertex| {
+                    vertex.position[0] += instance.offset[0];
+                    vertex.position[1] += instance.offset[1];
+                    vertex
+                }),
+            );
+        }
+        vertices
+    }
+}
@@ -0,0 +1,129 @@
+use crate::settings::{PostEffect, PostSettings};
+use crate::view::video::atlas::Image;
+
+pub const SCANLINES: f32 = 0.35;
+pub const CURVATURE: f32 = 0.08;
+pub const BLOOM_THRESHOLD: f32 = 0.6;
+pub const BLOOM_STRENGTH: f32 = 0.6;
+pub const VIGNETTE: f32 = 0.35;
+/// How far apart bloom samples are, in pixels, out to two either side
+pub const BLOOM_SPREAD: i32 = 2;
+
+/// Run `image` through every effect in the chain, or leave it be if post-processing's off
+///
+/// Does the same as the `post_*.glsl` shaders, for drawing without a GPU.
+pub fn apply(image: &Image, settings: &PostSettings) -> Image {
+    let mut image = image.clone();
+    if !settings.enabled {
+        return image;
+    }
+    for effect in settings.chain.iter() {
+        image = match effect {
+            PostEffect::Bloom => bloom(&image, settings),
+            PostEffect::Crt => crt(&image, settings),
+            PostEffect::Vignette => vignette(&image, settings),
+        };
+    }
+    image
+}
+
+fn bloom(image: &Image, settings: &PostSettings) -> Image {
+    map_pixels(image, |x, y| {
+        let mut glow = [0.0; 3];
+        let mut total_weight = 0.0;
+        for dy in -2..=2 {
+            for dx in -2..=2 {
+                let weight = bloom_weight(dx, dy);
+                let sample = pixel(
+                    image,
+                    x as i32 + dx * BLOOM_SPREAD,
+                    y as i32 + dy * BLOOM_SPREAD,
+                );
+                for (glow, c) in glow.iter_mut().zip(sample) {
+                    *glow += (c - settings.bloom_threshold).max(0.0) * weight;
+                }
+                total_weight += weight;
+            }
+        }
+
+        let color = pixel(image, x as i32, y as i32);
+        [0, 1, 2].map(|i| color[i] + glow[i] / total_weight * settings.bloom_strength)
+    })
+}
+
+/// Falls off with distance, same as in `post_bloom.glsl`
+fn bloom_weight(dx: i32, dy: i32) -> f32 {
+    1.0 / (1.0 + (dx * dx + dy * dy) as f32)
+}
+
+fn crt(image: &Image, settings: &PostSettings) -> Image {
+    map_pixels(image, |x, y| {
+        let [u, v] = uv(image, x, y);
+        let Some([u, v]) = curved([u, v], settings.curvature) else {
+            return [0.0; 3];
+        };
+        let color = pixel(
+            image,
+            (u * image.width as f32) as i32,
+            (v * image.height as f32) as i32,
+        );
+        let scanline = scanline(y, settings.scanlines);
+        color.map(|c| c * scanline)
+    })
+}
+
+/// Bulge `uv` out from the middle like the glass of an old screen, `None` if it falls off the edge
+pub fn curved([u, v]: [f32; 2], curvature: f32) -> Option<[f32; 2]> {
+    let (x, y) = (u * 2.0 - 1.0, v * 2.0 - 1.0);
+    let (x, y) = (x + x * y * y * curvature, y + y * x * x * curvature);
+    let (u, v) = ((x + 1.0) / 2.0, (y + 1.0) / 2.0);
+    ((0.0..1.0).contains(&u) && (0.0..1.0).contains(&v)).then_some([u, v])
+}
+
+/// Every other row darkened by `strength`
+fn scanline(row: u32, strength: f32) -> f32 {
+    let lit = (row % 2) as f32;
+    1.0 - strength * (1.0 - lit)
+}
+
+fn vignette(image: &Image, settings: &PostSettings) -> Image {
+    map_pixels(image, |x, y| {
+        let [u, v] = uv(image, x, y);
+        let (dx, dy) = (u * 2.0 - 1.0, v * 2.0 - 1.0);
+        // 0 in the middle, 1 in the corners
+        let distance = (dx * dx + dy * dy) / 2.0;
+        let shade = 1.0 - settings.vignette * distance;
+        pixel(image, x as i32, y as i32).map(|c| c * shade)
+    })
+}
+
+fn uv(image: &Image, x: u32, y: u32) -> [f32; 2] {
+    [
+        (x as f32 + 0.5) / image.width as f32,
+        (y as f32 + 0.5) / image.height as f32,
+    ]
+}
+
+/// A pixel's color from 0 to 1, clamped to the edge like the shaders' textures are
+fn pixel(image: &Image, x: i32, y: i32) -> [f32; 3] {
+    let x = x.clamp(0, image.width as i32 - 1) as usize;
+    let y = y.clamp(0, image.height as i32 - 1) as usize;
+    let index = (y * image.width as usize + x) * 4;
+    [0, 1, 2].map(|i| image.pixels[index + i] as f32 / 255.0)
+}
+
+fn map_pixels(image: &Image, mut f: impl FnMut(u32, u32) -> [f32; 3]) -> Image {
+    let mut pixels = Vec::with_capacity(image.pixels.len());
+    for y in 0..image.height {
+        for x in 0..image.width {
+            let color = f(x, y);
+            pixels.extend(color.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8));
+            pixels.push(u8::MAX);
+        }
+    }
+    Image {
+        width: image.width,
+        height: image.height,
+        pixels,
+    }
+}
@@ -0,0 +1,27 @@
+#version 330 core
+uniform sampler2D source;
+uniform vec2 resolution;
+uniform float threshold;
+uniform float strength;
+uniform float spread;
+
+in vec2 f_tex_coords;
+
+out vec4 FragColor;
+
+void main() {
+    vec3 glow = vec3(0.0);
+    float total_weight = 0.0;
+    for (int dy = -2; dy <= 2; dy++) {
+        for (int dx = -2; dx <= 2; dx++) {
+            float weight = 1.0 / (1.0 + float(dx * dx + dy * dy));
+            vec2 offset = vec2(dx, dy) * spread / resolution;
+            vec3 tap = texture(source, f_tex_coords + offset).rgb;
+            glow += max(tap - threshold, 0.0) * weight;
+            total_weight += weight;
+        }
+    }
+
+    vec3 color = texture(source, f_tex_coords).rgb;
+    FragColor = vec4(color + glow / total_weight * strength, 1.0);
+}
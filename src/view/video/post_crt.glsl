@@ -0,0 +1,22 @@
+#version 330 core
+uniform sampler2D source;
+uniform float curvature;
+uniform float scanlines;
+
+in vec2 f_tex_coords;
+
+out vec4 FragColor;
+
+void main() {
+    vec2 centered = f_tex_coords * 2.0 - 1.0;
+    centered += centered * centered.yx * centered.yx * curvature;
+    vec2 uv = (centered + 1.0) / 2.0;
+    if (uv.x < 0.0 || uv.x >= 1.0 || uv.y < 0.0 || uv.y >= 1.0) {
+        FragColor = vec4(0.0, 0.0, 0.0, 1.0);
+        return;
+    }
+
+    float lit = mod(floor(gl_FragCoord.y), 2.0);
+    float scanline = 1.0 - scanlines * (1.0 - lit);
+    FragColor = vec4(texture(source, uv).rgb * scanline, 1.0);
+}
@@ -0,0 +1,10 @@
+#version 330 core
+in vec3 position;
+in vec2 tex_coords;
+
+out vec2 f_tex_coords;
+
+void main() {
+    gl_Position = vec4(position.xy, 0.0, 1.0);
+    f_tex_coords = tex_coords;
+}
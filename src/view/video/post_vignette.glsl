@@ -0,0 +1,14 @@
+#version 330 core
+uniform sampler2D source;
+uniform float vignette;
+
+in vec2 f_tex_coords;
+
+out vec4 FragColor;
+
+void main() {
+    vec2 centered = f_tex_coords * 2.0 - 1.0;
+    // 0 in the middle, 1 in the corners
+    float distance = dot(centered, centered) / 2.0;
+    FragColor = vec4(texture(source, f_tex_coords).rgb * (1.0 - vignette * distance), 1.0);
+}
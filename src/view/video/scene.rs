@@ -0,0 +1,257 @@
+use std::time::Instant;
+
+use crate::logic::game_objs::GameObjectKind;
+use crate::logic::{interaction::Bounce, LogicState};
+use crate::settings::{self, Settings};
+use crate::view::video::atlas::Atlas;
+use crate::view::video::batch::brick_instances;
+use crate::view::video::camera::{self, Camera};
+use crate::view::video::draw_list::{CacheKey, DrawCommand, DrawList};
+use crate::view::video::particles::Particles;
+use crate::view::video::theme::Theme;
+use crate::view::video::trail::Trail;
+use crate::view::video::{pause_overlay, Drawable, Vertex};
+
+/// Everything the view adds on top of the game itself, which any renderer can draw
+#[derive(Clone, Debug)]
+pub struct Scene {
+    particles: Option<Particles>,
+    trail: Option<Trail>,
+    camera: Camera,
+    /// Only if an atlas was given and it loaded
+    atlas: Option<Atlas>,
+    /// Whether to mark unchanging geometry for caching and instance repeated quads
+    batching: bool,
+    /// Built in, then loaded from the themes directory
+    themes: Vec<Theme>,
+    theme: usize,
+    brick_rows: usize,
+    /// How many bricks a level starts with
+    brick_count: usize,
+    last_frame: Option<Instant>,
+}
+impl Scene {
+    pub fn new(settings: &Settings) -> Scene {
+        // no atlas isn't worth stopping over, everything has a flat model to fall back on
+        let atlas =
+            settings
+                .video
+                .sprite_atlas
+                .as_deref()
+                .and_then(|path| match Atlas::load(path) {
+                    Ok(atlas) => Some(atlas),
+                    Err(e) => {
+                        eprintln!("warning: {}, drawing without sprites", e);
+                        None
+                    }
+                });
+
+        let theme_dir = settings
+            .video
+            .theme_dir
+            .clone()
+            .or_else(|| Some(settings::config_dir()?.join("themes")));
+        let themes = Theme::all(&settings.logic, theme_dir.as_deref());
+        let theme = themes
+            .iter()
+            .position(|theme| theme.name == settings.video.theme)
+            .unwrap_or_else(|| {
+                eprintln!(
+                    "warning: there's no theme called '{}', using classic",
+                    settings.video.theme
+                );
+                0
+            });
+
+        Scene {
+            particles: settings
+                .video
+                .particles
+                .then(|| Particles::new(settings.video.max_particles)),
+            trail: settings
+                .video
+                .ball_trail
+                .then(|| Trail::new(settings.video.trail_length)),
+            camera: Camera::new(if settings.video.reduced_motion {
+                0.0
+            } else {
+                settings.video.screen_shake
+            }),
+            atlas,
+            batching: settings.video.persistent_buffers,
+            themes,
+            theme,
+            brick_rows: settings.logic.brick_rows,
+            brick_count: settings.logic.brick_rows * settings.logic.brick_columns,
+            last_frame: None,
+        }
+    }
+
+    pub fn atlas(&self) -> Option<&Atlas> {
+        self.atlas.as_ref()
+    }
+
+    pub fn batching(&self) -> bool {
+        self.batching
+    }
+
+    pub fn theme(&self) -> &Theme {
+        &self.themes[self.theme]
+    }
+
+    /// Switch to the next theme, round to the first after the last
+    pub fn next_theme(&mut self) {
+        self.theme = (self.theme + 1) % self.themes.len();
+    }
+
+    /// Move effects along to `now`, and start new ones for whatever just happened in the game
+    pub fn update(&mut self, logic_state: &LogicState, now: Instant) {
+        let delta_t = match self.last_frame {
+            Some(then) => now.duration_since(then).as_secs_f32(),
+            None => 0.0,
+        };
+        self.last_frame = Some(now);
+
+        // frozen, same as everything else
+        if logic_state.paused() {
+            return;
+        }
+
+        if let Some(particles) = &mut self.particles {
+            update_particles(
+                particles,
+                logic_state,
+                &self.themes[self.theme],
+                self.brick_rows,
+                delta_t,
+            );
+        }
+        if let Some(trail) = &mut self.trail {
+            trail.record(logic_state.ball().x, logic_state.ball().y);
+        }
+        update_camera(&mut self.camera, logic_state, delta_t);
+    }
+
+    /// Everything to draw this frame, back to front
+    pub fn draw_list(&self, logic_state: &LogicState) -> DrawList {
+        let mut draw_list = DrawList::new(self.camera.matrix());
+        let theme = self.theme();
+
+        let mut vertices = Vec::new();
+        let mut textured_vertices = Vec::new();
+        let mut playfield = None;
+        let mut bricks = Vec::new();
+        for game_obj in logic_state.game_objs() {
+            if let Some(textured) = self
+                .atlas
+                .as_ref()
+                .and_then(|atlas| game_obj.get_textured_vertices(atlas))
+            {
+                textured_vertices.extend(textured);
+                continue;
+            }
+            let color = theme.color(game_obj, self.brick_rows);
+            match (self.batching, &game_obj.kind) {
+                (true, GameObjectKind::Playfield) => {
+                    playfield = Some(painted(game_obj.get_vertices(), color))
+                }
+                (true, GameObjectKind::Brick(_)) => bricks.push((game_obj, color)),
+                _ => vertices.extend(painted(game_obj.get_vertices(), color)),
+            }
+        }
+
+        if let Some(playfield) = playfield {
+            draw_list.push(DrawCommand::Triangles {
+                vertices: playfield,
+                cache: Some(CacheKey {
+                    name: "playfield",
+                    version: self.theme,
+                }),
+            });
+        }
+        // bricks only ever disappear, so the count is as good as a version, as long as the
+        // theme stays the same
+        draw_list.push(DrawCommand::Quads {
+            cache: Some(CacheKey {
+                name: "bricks",
+                version: self.theme * (self.brick_count + 1) + bricks.len(),
+            }),
+            instances: brick_instances(bricks.into_iter()),
+        });
+        draw_list.push(DrawCommand::Triangles {
+            vertices,
+            cache: None,
+        });
+
+        if let Some(trail) = &self.trail {
+            let ball = logic_state.ball();
+            let color = theme.color(ball, self.brick_rows);
+            draw_list.push(DrawCommand::Blended(trail.vertices(ball, color)));
+        }
+
+        draw_list.push(DrawCommand::Sprites(textured_vertices));
+
+        // all of them in one go
+        if let Some(particles) = &self.particles {
+            draw_list.push(if self.batching {
+                DrawCommand::Quads {
+                    instances: particles.instances(),
+                    cache: None,
+                }
+            } else {
+                DrawCommand::Triangles {
+                    vertices: particles.vertices(),
+                    cache: None,
+                }
+            });
+        }
+
+        if logic_state.paused() {
+            draw_list.push(DrawCommand::Triangles {
+                vertices: pause_overlay(),
+                cache: None,
+            });
+        }
+
+        draw_list
+    }
+}
+
+/// Spawn particles for whatever just happened in the game, and move the rest along
+fn update_particles(
+    particles: &mut Particles,
+    logic_state: &LogicState,
+    theme: &Theme,
+    brick_rows: usize,
+    delta_t: f32,
+) {
+    for brick in logic_state.destroyed_bricks() {
+        particles.spawn_debris(brick, theme.color(brick, brick_rows));
+    }
+    for impact in logic_state.impacts() {
+        if impact.bounce != Bounce::Brick {
+            particles.spawn_sparks(impact.x, impact.y, theme.sparks);
+        }
+    }
+
+    particles.update(delta_t);
+}
+
+/// `vertices` all in the theme's `color`, rather than the one the game gave them
+fn painted(mut vertices: Vec<Vertex>, color: [f32; 3]) -> Vec<Vertex> {
+    for vertex in vertices.iter_mut() {
+        vertex.color = color;
+    }
+    vertices
+}
+
+/// Shake things up when something big happens
+fn update_camera(camera: &mut Camera, logic_state: &LogicState, delta_t: f32) {
+    for _ in logic_state.destroyed_bricks() {
+        camera.add_trauma(camera::BRICK_TRAUMA);
+    }
+    if logic_state.ball_lost() {
+        camera.add_trauma(camera::BALL_LOST_TRAUMA);
+    }
+    camera.update(delta_t);
+}
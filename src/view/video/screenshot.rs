@@ -0,0 +1,81 @@
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufWriter};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::view::video::atlas::{encode_png, Image};
+
+#[derive(Debug)]
+pub enum ScreenshotError {
+    Io(PathBuf, io::Error),
+    Png(PathBuf, png::EncodingError),
+    /// The renderer can't read frames back
+    Unsupported,
+}
+impl fmt::Display for ScreenshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScreenshotError::Io(path, e) => {
+                write!(f, "unable to write {}: {}", path.display(), e)
+            }
+            ScreenshotError::Png(path, e) => {
+                write!(f, "unable to encode {}: {}", path.display(), e)
+            }
+            ScreenshotError::Unsupported => write!(f, "screenshots aren't supported here"),
+        }
+    }
+}
+impl Error for ScreenshotError {}
+
+/// Save `image` in `dir` as a PNG named after the time it was taken, returning where it went
+pub fn save(image: &Image, dir: &Path) -> Result<PathBuf, ScreenshotError> {
+    fs::create_dir_all(dir).map_err(|e| ScreenshotError::Io(dir.to_owned(), e))?;
+
+    let path = dir.join(file_name(SystemTime::now()));
+    let file = File::create(&path).map_err(|e| ScreenshotError::Io(path.clone(), e))?;
+    encode_png(image, BufWriter::new(file)).map_err(|e| ScreenshotError::Png(path.clone(), e))?;
+    Ok(path)
+}
+
+/// e.g. `breakout-2024-03-09-141502-250.png`, in UTC, down to the millisecond so screenshots
+/// taken in quick succession don't overwrite each other
+pub fn file_name(time: SystemTime) -> String {
+    let since_epoch = time
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default();
+    let secs = since_epoch.as_secs();
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    let secs_of_day = secs % 86_400;
+
+    format!(
+        "breakout-{:04}-{:02}-{:02}-{:02}{:02}{:02}-{:03}.png",
+        year,
+        month,
+        day,
+        secs_of_day / 3600,
+        secs_of_day / 60 % 60,
+        secs_of_day % 60,
+        since_epoch.subsec_millis()
+    )
+}
+
+/// Year, month and day of the `days`th day since 1970-01-01, from Howard Hinnant's algorithm
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
@@ -0,0 +1,252 @@
+use crate::settings::PostSettings;
+use crate::view::video::atlas::{Atlas, Image};
+use crate::view::video::batch::Instance;
+use crate::view::video::draw_list::{DrawCommand, DrawList};
+use crate::view::video::{post, quad, Renderer};
+
+/// What `frag.glsl` and friends see for every pixel: a color with alpha, and texture coordinates
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Corner {
+    position: [f32; 2],
+    color: [f32; 4],
+    tex_coords: [f32; 2],
+}
+
+/// Draws into an RGBA image in memory, the same as the OpenGL backend would to a window, so
+/// frames can be checked without a GPU
+#[derive(Clone, Debug)]
+pub struct SoftwareRenderer {
+    image: Image,
+    atlas: Option<Atlas>,
+    post: PostSettings,
+}
+impl SoftwareRenderer {
+    pub fn new(width: u32, height: u32) -> SoftwareRenderer {
+        SoftwareRenderer {
+            image: Image {
+                width,
+                height,
+                pixels: vec![0; width as usize * height as usize * 4],
+            },
+            atlas: None,
+            post: PostSettings::default(),
+        }
+    }
+
+    /// Post-process every frame, once it's drawn
+    pub fn with_post(self, post: PostSettings) -> SoftwareRenderer {
+        SoftwareRenderer { post, ..self }
+    }
+
+    /// The last frame rendered
+    pub fn image(&self) -> &Image {
+        &self.image
+    }
+
+    /// From playfield coordinates to pixels, the same way `vert.glsl` does
+    fn to_pixels(&self, camera: &[[f32; 4]; 4], [x, y]: [f32; 2]) -> [f32; 2] {
+        let mut x_clip = camera[0][0] * x + camera[1][0] * y + camera[3][0];
+        let mut y_clip = camera[0][1] * x + camera[1][1] * y + camera[3][1];
+
+        let window_aspect = self.image.width as f32 / self.image.height as f32;
+        if window_aspect > 1.0 {
+            x_clip /= window_aspect;
+        } else {
+            y_clip *= window_aspect;
+        }
+
+        [
+            (x_clip + 1.0) / 2.0 * self.image.width as f32,
+            (1.0 - y_clip) / 2.0 * self.image.height as f32,
+        ]
+    }
+
+    fn draw_triangles(&mut self, camera: &[[f32; 4]; 4], corners: &[Corner], textured: bool) {
+        for triangle in corners.chunks_exact(3) {
+            let mut triangle = [triangle[0], triangle[1], triangle[2]];
+            for corner in triangle.iter_mut() {
+                corner.position = self.to_pixels(camera, corner.position);
+            }
+            self.fill_triangle(&triangle, textured);
+        }
+    }
+
+    /// Fill every pixel whose center is inside `triangle`, already in pixel coordinates
+    fn fill_triangle(&mut self, triangle: &[Corner; 3], textured: bool) {
+        let [a, b, c] = triangle.map(|corner| corner.position);
+        let area = edge(a, b, c);
+        if area == 0.0 {
+            return;
+        }
+
+        let (width, height) = (self.image.width as f32, self.image.height as f32);
+        let left = a[0].min(b[0]).min(c[0]).floor().clamp(0.0, width) as u32;
+        let right = a[0].max(b[0]).max(c[0]).ceil().clamp(0.0, width) as u32;
+        let top = a[1].min(b[1]).min(c[1]).floor().clamp(0.0, height) as u32;
+        let bottom = a[1].max(b[1]).max(c[1]).ceil().clamp(0.0, height) as u32;
+
+        for y in top..bottom {
+            for x in left..right {
+                let p = [x as f32 + 0.5, y as f32 + 0.5];
+                // barycentric weights, all the same sign as the area when inside
+                let weights = [
+                    edge(b, c, p) / area,
+                    edge(c, a, p) / area,
+                    edge(a, b, p) / area,
+                ];
+                if weights.iter().any(|&weight| weight < 0.0) {
+                    continue;
+                }
+                // pixels exactly on an edge shared by two triangles only belong to one of them
+                if weights
+                    .iter()
+                    .zip([(b, c), (c, a), (a, b)])
+                    .any(|(&weight, (from, to))| weight == 0.0 && !is_top_left(from, to, area))
+                {
+                    continue;
+                }
+
+                let mut color = [0.0; 4];
+                let mut tex_coords = [0.0; 2];
+                for (corner, weight) in triangle.iter().zip(weights) {
+                    for (channel, corner_channel) in color.iter_mut().zip(corner.color) {
+                        *channel += corner_channel * weight;
+                    }
+                    for (coord, corner_coord) in tex_coords.iter_mut().zip(corner.tex_coords) {
+                        *coord += corner_coord * weight;
+                    }
+                }
+                if textured {
+                    match self.sample(tex_coords) {
+                        Some(texel) => color = texel,
+                        None => continue,
+                    }
+                }
+
+                self.blend(x, y, color);
+            }
+        }
+    }
+
+    /// Nearest texel of the atlas, same as the sampler in `GliumRenderer`
+    fn sample(&self, [u, v]: [f32; 2]) -> Option<[f32; 4]> {
+        let image = &self.atlas.as_ref()?.image;
+        let x = ((u * image.width as f32) as u32).min(image.width - 1);
+        // texture coordinates start at the bottom, rows at the top
+        let y = (((1.0 - v) * image.height as f32) as u32).min(image.height - 1);
+        let index = (y * image.width + x) as usize * 4;
+        let texel = &image.pixels[index..index + 4];
+        Some([0, 1, 2, 3].map(|i| texel[i] as f32 / 255.0))
+    }
+
+    /// Alpha blend `color` over the pixel at `x`, `y`
+    fn blend(&mut self, x: u32, y: u32, color: [f32; 4]) {
+        let index = (y * self.image.width + x) as usize * 4;
+        let pixel = &mut self.image.pixels[index..index + 4];
+        let alpha = color[3].clamp(0.0, 1.0);
+        for i in 0..3 {
+            let under = pixel[i] as f32 / 255.0;
+            let over = color[i].clamp(0.0, 1.0);
+            pixel[i] = ((over * alpha + under * (1.0 - alpha)) * 255.0).round() as u8;
+        }
+        pixel[3] = u8::MAX;
+    }
+}
+impl Renderer for SoftwareRenderer {
+    fn set_atlas(&mut self, atlas: &Atlas) {
+        self.atlas = Some(atlas.clone());
+    }
+
+    fn render(&mut self, draw_list: &DrawList) {
+        // clear to black
+        for pixel in self.image.pixels.chunks_exact_mut(4) {
+            pixel.copy_from_slice(&[0, 0, 0, u8::MAX]);
+        }
+
+        let camera = &draw_list.camera;
+        for command in draw_list.commands.iter() {
+            let (corners, textured): (Vec<Corner>, bool) = match command {
+                DrawCommand::Triangles { vertices, .. } => (
+                    vertices
+                        .iter()
+                        .map(|vertex| Corner {
+                            position: [vertex.position[0], vertex.position[1]],
+                            color: [vertex.color[0], vertex.color[1], vertex.color[2], 1.0],
+                            tex_coords: [0.0; 2],
+                        })
+                        .collect(),
+                    false,
+                ),
+                DrawCommand::Quads { instances, .. } => (instance_corners(instances), false),
+                DrawCommand::Text(text) => (instance_corners(&text.instances()), false),
+                DrawCommand::Blended(vertices) => (
+                    vertices
+                        .iter()
+                        .map(|vertex| Corner {
+                            position: [vertex.position[0], vertex.position[1]],
+                            color: vertex.color,
+                            tex_coords: [0.0; 2],
+                        })
+                        .collect(),
+                    false,
+                ),
+                DrawCommand::Sprites(vertices) => (
+                    vertices
+                        .iter()
+                        .map(|vertex| Corner {
+                            position: [vertex.position[0], vertex.position[1]],
+                            color: [1.0; 4],
+                            tex_coords: vertex.tex_coords,
+                        })
+                        .collect(),
+                    true,
+                ),
+            };
+            self.draw_triangles(camera, &corners, textured);
+        }
+
+        if self.post.enabled {
+            self.image = post::apply(&self.image, &self.post);
+        }
+    }
+
+    fn present(&mut self) {}
+
+    fn size(&self) -> (f32, f32) {
+        (self.image.width as f32, self.image.height as f32)
+    }
+
+    fn screenshot(&self) -> Option<Image> {
+        Some(self.image.clone())
+    }
+}
+
+/// The unit quad for every instance, the same as `instance_vert.glsl` does
+fn instance_corners(instances: &[Instance]) -> Vec<Corner> {
+    let mut corners = Vec::with_capacity(instances.len() * 6);
+    for instance in instances {
+        corners.extend(quad(1.0, 1.0, instance.color).map(|vertex| Corner {
+            position: [
+                vertex.position[0] * instance.size[0] + instance.offset[0],
+                vertex.position[1] * instance.size[1] + instance.offset[1],
+            ],
+            color: [instance.color[0], instance.color[1], instance.color[2], 1.0],
+            tex_coords: [0.0; 2],
+        }));
+    }
+    corners
+}
+
+/// Twice the signed area of `a`, `b`, `p`, positive if `p` is to the right of `a` to `b`
+fn edge(a: [f32; 2], b: [f32; 2], p: [f32; 2]) -> f32 {
+    (p[0] - a[0]) * (b[1] - a[1]) - (p[1] - a[1]) * (b[0] - a[0])
+}
+
+/// Whether the edge `from` to `to` is a top or left edge of a triangle wound like `area`
+fn is_top_left(from: [f32; 2], to: [f32; 2], area: f32) -> bool {
+    // make every triangle wind the same way, so top and left mean the same thing for all of them
+    let (from, to) = if area > 0.0 { (from, to) } else { (to, from) };
+    let top = from[1] == to[1] && to[0] < from[0];
+    let left = to[1] > from[1];
+    top || left
+}
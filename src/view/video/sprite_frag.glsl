@@ -0,0 +1,10 @@
+#version 330 core
+uniform sampler2D atlas;
+
+in vec2 f_tex_coords;
+
+out vec4 FragColor;
+
+void main() {
+    FragColor = texture(atlas, f_tex_coords);
+}
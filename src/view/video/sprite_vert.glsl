@@ -0,0 +1,20 @@
+#version 330 core
+uniform float window_aspect;
+uniform mat4 camera;
+
+in vec3 position;
+in vec2 tex_coords;
+
+out vec2 f_tex_coords;
+
+void main() {
+    gl_Position = camera * vec4(position.xyz, 1.0);
+
+    if (window_aspect > 1.0) {
+        gl_Position.x /= window_aspect;
+    } else {
+        gl_Position.y *= window_aspect;
+    }
+
+    f_tex_coords = tex_coords;
+}
@@ -0,0 +1,172 @@
+use crossterm::{
+    cursor, event, execute, queue,
+    style::{self, Color},
+    terminal,
+};
+use std::io::{self, Stdout, Write};
+
+use crate::console;
+use crate::view::video::atlas::{Atlas, Image};
+use crate::view::video::draw_list::DrawList;
+use crate::view::video::software::SoftwareRenderer;
+use crate::view::video::Renderer;
+
+/// Upper half block, colored with the top pixel in front and the bottom one behind
+pub const HALF_BLOCK: char = '\u{2580}';
+
+/// Colors of the top and bottom pixel of a character cell
+pub type Cell = ([u8; 3], [u8; 3]);
+
+/// Draws into the terminal with half blocks and 24-bit ANSI colors, two pixels per character
+///
+/// Takes over the terminal while it's alive: raw mode, the alternate screen, mouse capture.
+/// Everything is put back when it's dropped, even while panicking, and anything printed
+/// meanwhile is printed then.
+pub struct TerminalRenderer {
+    software: SoftwareRenderer,
+    /// Kept to hand to a new software renderer when the terminal's resized
+    atlas: Option<Atlas>,
+    /// What's on screen already, so only changed cells are sent
+    cells: Vec<Cell>,
+    stdout: Stdout,
+}
+impl TerminalRenderer {
+    pub fn new() -> io::Result<TerminalRenderer> {
+        let mut stdout = io::stdout();
+        terminal::enable_raw_mode()?;
+        execute!(
+            stdout,
+            terminal::EnterAlternateScreen,
+            cursor::Hide,
+            event::EnableMouseCapture,
+            event::EnableFocusChange,
+        )?;
+
+        // anything printed now would end up drawn over the game
+        console::hold();
+
+        let (columns, rows) = terminal::size()?;
+        Ok(TerminalRenderer {
+            software: SoftwareRenderer::new(columns as u32, rows as u32 * 2),
+            atlas: None,
+            cells: Vec::new(),
+            stdout,
+        })
+    }
+
+    fn draw_cells(&mut self, cells: &[Cell]) -> io::Result<()> {
+        let columns = self.software.image().width as usize;
+        let redraw = self.cells.len() != cells.len();
+        if redraw {
+            queue!(self.stdout, terminal::Clear(terminal::ClearType::All))?;
+        }
+
+        let mut colors = None;
+        let mut cursor = None;
+        for (index, &cell) in cells.iter().enumerate() {
+            if !redraw && self.cells[index] == cell {
+                continue;
+            }
+
+            let position = ((index % columns) as u16, (index / columns) as u16);
+            if cursor != Some(position) {
+                queue!(self.stdout, cursor::MoveTo(position.0, position.1))?;
+            }
+            if colors != Some(cell) {
+                let ([r, g, b], [r_bg, g_bg, b_bg]) = cell;
+                queue!(
+                    self.stdout,
+                    style::SetColors(style::Colors::new(
+                        Color::Rgb { r, g, b },
+                        Color::Rgb {
+                            r: r_bg,
+                            g: g_bg,
+                            b: b_bg,
+                        },
+                    )),
+                )?;
+                colors = Some(cell);
+            }
+            queue!(self.stdout, style::Print(HALF_BLOCK))?;
+            cursor = Some((position.0 + 1, position.1));
+        }
+        if colors.is_some() {
+            queue!(self.stdout, style::ResetColor)?;
+            self.stdout.flush()?;
+        }
+
+        self.cells = cells.to_vec();
+        Ok(())
+    }
+}
+impl Renderer for TerminalRenderer {
+    fn set_atlas(&mut self, atlas: &Atlas) {
+        self.software.set_atlas(atlas);
+        self.atlas = Some(atlas.clone());
+    }
+
+    fn render(&mut self, draw_list: &DrawList) {
+        // keep up with the terminal being resized
+        if let Ok((columns, rows)) = terminal::size()
+            && (columns as f32, rows as f32 * 2.0) != self.software.size()
+        {
+            self.software = SoftwareRenderer::new(columns as u32, rows as u32 * 2);
+            if let Some(atlas) = &self.atlas {
+                self.software.set_atlas(atlas);
+            }
+        }
+
+        self.software.render(draw_list);
+    }
+
+    fn present(&mut self) {
+        let cells = cells(self.software.image());
+        self.draw_cells(&cells)
+            .expect("unable to draw to the terminal, exiting");
+    }
+
+    fn size(&self) -> (f32, f32) {
+        self.software.size()
+    }
+
+    /// Full resolution, i.e. one pixel per half block
+    fn screenshot(&self) -> Option<Image> {
+        self.software.screenshot()
+    }
+}
+impl Drop for TerminalRenderer {
+    fn drop(&mut self) {
+        // nothing more to be done if these fail
+        let _ = execute!(
+            self.stdout,
+            style::ResetColor,
+            event::DisableFocusChange,
+            event::DisableMouseCapture,
+            cursor::Show,
+            terminal::LeaveAlternateScreen,
+        );
+        let _ = terminal::disable_raw_mode();
+        console::release();
+    }
+}
+
+/// Pair up rows of pixels into character cells, top row first
+pub fn cells(image: &Image) -> Vec<Cell> {
+    let width = image.width as usize;
+    let pixel = |x: usize, y: usize| {
+        let index = (y * width + x) * 4;
+        [
+            image.pixels[index],
+            image.pixels[index + 1],
+            image.pixels[index + 2],
+        ]
+    };
+
+    let mut cells = Vec::with_capacity(width * image.height as usize / 2);
+    for row in 0..image.height as usize / 2 {
+        for x in 0..width {
+            cells.push((pixel(x, row * 2), pixel(x, row * 2 + 1)));
+        }
+    }
+    cells
+}
@@ -0,0 +1,212 @@
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::logic::game_objs::{GameObject, GameObjectKind};
+use crate::settings::LogicSettings;
+use crate::view::video::particles::SPARK_COLOR;
+
+/// Colors for everything on screen, read from a TOML file like:
+///
+/// ```toml
+/// name = "dusk"
+/// playfield = [0.1, 0.1, 0.2]
+/// paddle = [1.0, 0.6, 0.2]
+/// ball = [1.0, 1.0, 1.0]
+/// bricks = [[0.9, 0.3, 0.3], [0.3, 0.6, 0.9]] # top row first, repeating all the way down
+/// sparks = [1.0, 0.8, 0.2]
+/// ```
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Theme {
+    pub name: String,
+    pub playfield: [f32; 3],
+    pub paddle: [f32; 3],
+    pub ball: [f32; 3],
+    pub bricks: Vec<[f32; 3]>,
+    #[serde(default = "spark_color")]
+    pub sparks: [f32; 3],
+}
+
+fn spark_color() -> [f32; 3] {
+    SPARK_COLOR
+}
+
+#[derive(Debug)]
+pub enum ThemeError {
+    Io(PathBuf, io::Error),
+    Parse(PathBuf, toml::de::Error),
+    Invalid(PathBuf, &'static str),
+}
+impl fmt::Display for ThemeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ThemeError::Io(path, e) => write!(f, "unable to read {}: {}", path.display(), e),
+            ThemeError::Parse(path, e) => write!(f, "unable to parse {}: {}", path.display(), e),
+            ThemeError::Invalid(path, msg) => write!(f, "bad theme {}: {}", path.display(), msg),
+        }
+    }
+}
+impl Error for ThemeError {}
+
+impl Theme {
+    /// Whatever colors `[logic]` in the config file has, the defaults if it has none
+    pub fn classic(settings: &LogicSettings) -> Theme {
+        Theme {
+            name: "classic".to_owned(),
+            playfield: settings.playfield_color,
+            paddle: settings.paddle_color,
+            ball: settings.ball_color,
+            bricks: vec![settings.brick_color],
+            sparks: SPARK_COLOR,
+        }
+    }
+
+    /// Every theme that comes with the game, classic first
+    pub fn builtin(settings: &LogicSettings) -> Vec<Theme> {
+        // the okabe-ito palette, which stays distinct with any kind of color blindness
+        const ORANGE: [f32; 3] = [0.902, 0.624, 0.0];
+        const SKY_BLUE: [f32; 3] = [0.337, 0.706, 0.914];
+        const BLUISH_GREEN: [f32; 3] = [0.0, 0.620, 0.451];
+        const YELLOW: [f32; 3] = [0.941, 0.894, 0.259];
+        const BLUE: [f32; 3] = [0.0, 0.447, 0.698];
+        const VERMILLION: [f32; 3] = [0.835, 0.369, 0.0];
+        const REDDISH_PURPLE: [f32; 3] = [0.8, 0.475, 0.655];
+        const WHITE: [f32; 3] = [1.0; 3];
+        const BLACK: [f32; 3] = [0.0; 3];
+        const DARK_GREY: [f32; 3] = [0.1, 0.1, 0.12];
+
+        vec![
+            Theme::classic(settings),
+            Theme {
+                name: "dark".to_owned(),
+                playfield: DARK_GREY,
+                paddle: [0.0, 0.9, 0.6],
+                ball: WHITE,
+                bricks: vec![
+                    [0.9, 0.3, 0.3],
+                    [0.95, 0.6, 0.2],
+                    [0.95, 0.85, 0.3],
+                    [0.4, 0.8, 0.4],
+                    [0.3, 0.6, 0.95],
+                    [0.6, 0.4, 0.9],
+                ],
+                sparks: SPARK_COLOR,
+            },
+            Theme {
+                name: "high-contrast".to_owned(),
+                playfield: BLACK,
+                paddle: WHITE,
+                ball: [1.0, 1.0, 0.0],
+                bricks: vec![WHITE, [0.0, 1.0, 1.0]],
+                sparks: [1.0, 1.0, 0.0],
+            },
+            // red and green look alike, so it's all blues against oranges and yellows
+            Theme {
+                name: "deuteranopia".to_owned(),
+                playfield: DARK_GREY,
+                paddle: SKY_BLUE,
+                ball: YELLOW,
+                bricks: vec![ORANGE, BLUE, YELLOW, SKY_BLUE],
+                sparks: WHITE,
+            },
+            // much the same, but reds look dark as well, so there's nothing near red
+            Theme {
+                name: "protanopia".to_owned(),
+                playfield: DARK_GREY,
+                paddle: YELLOW,
+                ball: WHITE,
+                bricks: vec![SKY_BLUE, ORANGE, BLUE, YELLOW],
+                sparks: SKY_BLUE,
+            },
+            // blue and yellow look alike instead
+            Theme {
+                name: "tritanopia".to_owned(),
+                playfield: DARK_GREY,
+                paddle: BLUISH_GREEN,
+                ball: WHITE,
+                bricks: vec![VERMILLION, BLUISH_GREEN, REDDISH_PURPLE],
+                sparks: VERMILLION,
+            },
+        ]
+    }
+
+    pub fn load(path: &Path) -> Result<Theme, ThemeError> {
+        let text = fs::read_to_string(path).map_err(|e| ThemeError::Io(path.to_owned(), e))?;
+        let theme: Theme =
+            toml::from_str(&text).map_err(|e| ThemeError::Parse(path.to_owned(), e))?;
+
+        if theme.bricks.is_empty() {
+            return Err(ThemeError::Invalid(path.to_owned(), "bricks need a color"));
+        }
+        if [theme.playfield, theme.paddle, theme.ball, theme.sparks]
+            .iter()
+            .chain(theme.bricks.iter())
+            .flatten()
+            .any(|c| !(0.0..=1.0).contains(c))
+        {
+            return Err(ThemeError::Invalid(
+                path.to_owned(),
+                "color components go from 0 to 1",
+            ));
+        }
+        Ok(theme)
+    }
+
+    /// The built in themes, then any `.toml` files in `dir`, in name order. Ones that won't load
+    /// are skipped with a warning.
+    pub fn all(settings: &LogicSettings, dir: Option<&Path>) -> Vec<Theme> {
+        let mut themes = Theme::builtin(settings);
+
+        let Some(dir) = dir else {
+            return themes;
+        };
+        let Ok(entries) = fs::read_dir(dir) else {
+            // no themes directory is fine, most people won't have one
+            return themes;
+        };
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| Some(entry.ok()?.path()))
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|extension| extension == "toml")
+            })
+            .collect();
+        paths.sort();
+
+        for path in paths {
+            match Theme::load(&path) {
+                Ok(theme) => themes.push(theme),
+                Err(e) => eprintln!("warning: {}, skipping it", e),
+            }
+        }
+        themes
+    }
+
+    /// The color for brick row `row`, counting down from the top
+    pub fn brick(&self, row: usize) -> [f32; 3] {
+        self.bricks[row % self.bricks.len()]
+    }
+
+    /// What color to draw `game_obj` in. `brick_rows` is how many rows the level has, to tell
+    /// which row a brick's on.
+    pub fn color(&self, game_obj: &GameObject, brick_rows: usize) -> [f32; 3] {
+        match game_obj.kind {
+            GameObjectKind::Paddle => self.paddle,
+            GameObjectKind::Ball => self.ball,
+            GameObjectKind::Playfield => self.playfield,
+            GameObjectKind::Brick(_) => self.brick(brick_row(game_obj.y, brick_rows)),
+        }
+    }
+}
+
+/// Which row a brick at `y` is on, counting down from the top, the other way round to how
+/// [`GameObject::bricks`] lays them out
+fn brick_row(y: f32, brick_rows: usize) -> usize {
+    let from_bottom = (y * brick_rows as f32 - 0.5).round().max(0.0) as usize;
+    brick_rows.saturating_sub(1 + from_bottom)
+}
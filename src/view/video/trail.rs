@@ -0,0 +1,81 @@
+use std::collections::VecDeque;
+
+use crate::logic::game_objs::GameObject;
+use crate::view::video::BlendedVertex;
+
+pub const TRAIL_LENGTH: usize = 12;
+/// Opacity of the newest trail quad, fading out to nothing at the oldest
+pub const TRAIL_ALPHA: f32 = 0.5;
+/// Anything further than this in one frame is a respawn rather than movement
+pub const TRAIL_MAX_JUMP: f32 = 0.25;
+
+/// Where the ball has been over the last few frames, newest first
+#[derive(Clone, Debug)]
+pub struct Trail {
+    positions: VecDeque<(f32, f32)>,
+    length: usize,
+}
+impl Trail {
+    pub fn new(length: usize) -> Trail {
+        Trail {
+            positions: VecDeque::with_capacity(length),
+            length,
+        }
+    }
+
+    pub fn record(&mut self, x: f32, y: f32) {
+        if let Some(&(last_x, last_y)) = self.positions.front()
+            && ((x - last_x).powi(2) + (y - last_y).powi(2)).sqrt() > TRAIL_MAX_JUMP
+        {
+            // don't smear a trail across the playfield when the ball respawns
+            self.positions.clear();
+        }
+
+        if self.positions.len() == self.length {
+            self.positions.pop_back();
+        }
+        if self.length > 0 {
+            self.positions.push_front((x, y));
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// A quad per past position in `color`, shrinking and fading the older they are
+    pub fn vertices(&self, ball: &GameObject, color: [f32; 3]) -> Vec<BlendedVertex> {
+        let mut vertices = Vec::with_capacity(self.positions.len() * 6);
+        // the newest position is the ball itself, so skip it
+        for (age, &(x, y)) in self.positions.iter().enumerate().skip(1) {
+            let fade = 1.0 - age as f32 / self.length as f32;
+            vertices.extend(blended_quad(
+                ball.width * fade,
+                ball.height * fade,
+                [color[0], color[1], color[2], TRAIL_ALPHA * fade],
+                (x, y),
+            ));
+        }
+        vertices
+    }
+}
+
+fn blended_quad(
+    width: f32,
+    height: f32,
+    color: [f32; 4],
+    (x, y): (f32, f32),
+) -> [BlendedVertex; 6] {
+    crate::view::video::quad(width, height, [0.0; 3]).map(|vertex| BlendedVertex {
+        position: [
+            vertex.position[0] + x,
+            vertex.position[1] + y,
+            vertex.position[2],
+        ],
+        color,
+    })
+}
@@ -1,5 +1,6 @@
 #version 330 core
 uniform float window_aspect;
+uniform mat4 camera;
 
 in vec3 position;
 in vec3 color;
@@ -7,7 +8,7 @@ in vec3 color;
 out vec3 f_color;
 
 void main() {
-    gl_Position = vec4(position.xyz, 1.0);
+    gl_Position = camera * vec4(position.xyz, 1.0);
 
     if (window_aspect > 1.0) {
         gl_Position.x /= window_aspect;